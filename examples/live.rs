@@ -20,16 +20,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     use clap::value_t_or_exit;
     let rtl = rtltcp::RtlTcp::new()
-        .address(matches.value_of("address").unwrap())
-        .rate(1800000 / 6)
-        .gain(None)
+        .address(matches.value_of("address").unwrap())?
+        .rate(1800000 / 6)?
+        .gain(None)?
         .rtlagc(true)
-        .frequency((value_t_or_exit!(matches, "FREQ", f32) * 1000000.0) as u32);
+        .frequency((value_t_or_exit!(matches, "FREQ", f32) * 1000000.0) as u32)?;
 
-    let sig = rtl.listen()?;
+    // without these, the dongle's DC spike and IQ image dominate
+    let sig = rtl.listen()?
+        .filter(filter::DcBlockDesign::new(0.1))
+        .filter(filter::IqBalanceDesign::new(1.0));
     let rate = sig.rate();
     let mut sig = sig.window(1000.0 / rate)
-        .decimate(fps as f32)
+        .downsample(fps as f32)
         .map(|w| {
             let win = w.borrow();
             let winsig = signal::from_iter(rate, win.iter().cloned());
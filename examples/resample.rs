@@ -0,0 +1,68 @@
+use sdr::*;
+use sdr::signal::{Freq, ResampleMode};
+
+use num::Complex;
+
+const FFT_SIZE: usize = 8192;
+
+// tone power over everything else, in dB
+fn snr<S>(signal: S, tone: f32) -> f32 where S: Signal<Sample=Complex<f32>> {
+    let rate = signal.rate();
+    let window = filter::Window::BlackmanHarris(FFT_SIZE + 1)
+        .coefficients(rate);
+    let data: Vec<_> = signal.skip(0.5).iter().take(FFT_SIZE)
+        .zip(window.iter()).map(|(v, w)| v * w).collect();
+    let spectrum = fft::fft(signal::from_iter(rate, data.into_iter()));
+
+    let (mut on, mut off) = (0.0, 0.0);
+    let bin = rate / FFT_SIZE as f32;
+    for (f, v) in spectrum {
+        if (f - tone).abs() <= 8.0 * bin {
+            on += v.norm_sqr();
+        } else {
+            off += v.norm_sqr();
+        }
+    }
+    10.0 * (on / off).log10()
+}
+
+// mean output power for a tone that should have been filtered out
+fn leakage<S>(signal: S) -> f32 where S: Signal<Sample=Complex<f32>> {
+    let values: Vec<_> = signal.skip(0.5).take(0.5).iter().collect();
+    let power = values.iter().map(|v| v.norm_sqr()).sum::<f32>()
+        / values.len() as f32;
+    10.0 * power.log10()
+}
+
+fn measure<M>(name: &str, mode: M)
+where
+    M: ResampleMode<Freq> + Copy,
+    M::Signal: Signal<Sample=Complex<f32>>,
+{
+    let tone = snr(signal::freq(44100.0, 1000.0, 0.0)
+                   .resample_with(mode, 48000.0), 1000.0);
+    let alias = leakage(signal::freq(96000.0, 30000.0, 0.0)
+                        .resample_with(mode, 48000.0));
+
+    let start = std::time::Instant::now();
+    let count = signal::freq(44100.0, 1000.0, 0.0)
+        .resample_with(mode, 48000.0)
+        .take(10.0).iter().count();
+    let speed = count as f64 / start.elapsed().as_secs_f64() / 1e6;
+
+    println!("{:<24} {:>8.1} {:>8.1} {:>8.2}", name, tone, alias, speed);
+}
+
+fn main() {
+    use resample::ConverterType::*;
+    println!("{:<24} {:>8} {:>8} {:>8}", "mode", "snr dB", "alias dB",
+             "MS/s");
+    for &typ in [SincBestQuality, SincMediumQuality, SincFastest,
+                 ZeroOrderHold, Linear].iter() {
+        measure(typ.name(), typ);
+    }
+    measure("polyphase", resample::Polyphase::new());
+    measure("polyphase 60dB", resample::Polyphase::new().attenuation(60.0));
+    measure("polyphase 120dB", resample::Polyphase::new().attenuation(120.0));
+    measure("polyphase narrow", resample::Polyphase::new().bandwidth(0.8));
+}
@@ -0,0 +1,58 @@
+use sdr::*;
+
+fn main() -> std::io::Result<()> {
+    let matches = clap::App::new("rtltcp server")
+        .about("pretend to be an rtl_tcp server")
+        .arg(clap::Arg::with_name("FILE")
+             .help("raw IQ file to serve, or a test FM broadcast if missing")
+             .index(1))
+        .arg(clap::Arg::with_name("address")
+             .help("the address to listen on")
+             .short("a")
+             .long("address")
+             .value_name("ADDRESS")
+             .default_value("localhost:1234")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("frequency")
+             .help("the frequency the file is centered on, in MHz")
+             .short("f")
+             .long("frequency")
+             .value_name("FREQ")
+             .default_value("100")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("rate")
+             .help("the sample rate of the file, in Hz")
+             .short("r")
+             .long("rate")
+             .value_name("RATE")
+             .default_value("1800000")
+             .takes_value(true))
+        .get_matches();
+
+    use clap::value_t_or_exit;
+    let address = matches.value_of("address").unwrap();
+    let frequency = value_t_or_exit!(matches, "frequency", f32) * 1000000.0;
+    let rate = value_t_or_exit!(matches, "rate", f32);
+
+    if let Some(path) = matches.value_of("FILE") {
+        let format = signal::IqFormat::from_extension(path)
+            .unwrap_or(signal::IqFormat::Cu8);
+        let iq = signal::from_file(path, format, rate)?;
+        let server = rtltcp::RtlTcpServer::bind(address, iq, frequency as u32)?
+            .throttle(true);
+        println!("serving {} on {}", path, server.local_addr()?);
+        Ok(server.serve()?)
+    } else {
+        // a 1kHz tone at full broadcast deviation
+        let (tone, deviation) = (1000.0, filter::WbfmDesign::DEVIATION);
+        let fm = signal::from_func(rate, move |t| {
+            let phase = deviation / tone
+                * (2.0 * std::f32::consts::PI * tone * t).sin();
+            Complex::from_polar(&0.5, &phase)
+        });
+        let server = rtltcp::RtlTcpServer::bind(address, fm, frequency as u32)?
+            .throttle(true);
+        println!("serving test tone on {}", server.local_addr()?);
+        Ok(server.serve()?)
+    }
+}
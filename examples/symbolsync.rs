@@ -0,0 +1,55 @@
+use sdr::*;
+use plotters::prelude::*;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let symbol_rate = 1000.0;
+    let sps = 4;
+    let pulse = filter::Pulse::RootRaisedCosine(0.35);
+
+    // a slightly fast transmitter, a third of a symbol late
+    let received = signal::modulate(signal::Modulation::Qpsk, symbol_rate,
+                                    sps, pulse, signal::prbs(1))
+        .resample_with(resample::Polyphase::new(),
+                       sps as f32 * symbol_rate * 0.999)
+        .skip(0.33 / symbol_rate)
+        .take(2.0);
+
+    let design = |detector| {
+        signal::SymbolSyncDesign::new(detector, symbol_rate)
+            .bandwidth(0.005 * symbol_rate)
+    };
+    let matched = || received.clone()
+        .filter(filter::PulseD(pulse, symbol_rate, 10));
+    let gardner = matched()
+        .symbol_sync(design(signal::TimingDetector::Gardner))
+        .map(|(_, error)| error);
+    let mm = matched()
+        .symbol_sync(design(signal::TimingDetector::MuellerMuller))
+        .map(|(_, error)| error);
+    let polyphase = received.clone()
+        .symbol_sync(design(signal::TimingDetector::Polyphase(pulse, 10)))
+        .map(|(_, error)| error);
+
+    let matches = plot::cli::setup(clap::App::new("symbolsync"))
+        .get_matches();
+
+    plot::cli::run(&matches, (640, 960), |root| {
+        root.fill(&WHITE)?;
+        let subs = root.split_evenly((3, 1));
+
+        for (sub, (name, errors)) in subs.iter().zip(vec![
+            ("Gardner", gardner.enumerate().collect::<Vec<_>>()),
+            ("Mueller-Muller", mm.enumerate().collect()),
+            ("Polyphase", polyphase.enumerate().collect()),
+        ]) {
+            let title = format!("{} Timing Error", name);
+            plot::Simple::on(sub)
+                .title(&title)
+                .xlabel("t")
+                .add_line(errors, None)
+                .draw()?;
+        }
+
+        Ok(())
+    })
+}
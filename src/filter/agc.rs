@@ -0,0 +1,157 @@
+use super::{coefficient, Filter, FilterDesign};
+
+use num::Complex;
+
+// anything the AGC knows how to measure and scale
+pub trait Magnitude: Copy + std::ops::Mul<f32, Output=Self> {
+    fn magnitude(&self) -> f32;
+}
+
+impl Magnitude for f32 {
+    fn magnitude(&self) -> f32 {
+        self.abs()
+    }
+}
+
+impl Magnitude for Complex<f32> {
+    fn magnitude(&self) -> f32 {
+        self.norm()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Detector {
+    // follows the envelope
+    Peak,
+    // follows the power, and aims the square root at the target
+    Rms,
+}
+
+#[derive(Clone, Debug)]
+pub struct AgcDesign {
+    attack: f32,
+    decay: f32,
+    hang: f32,
+    target: f32,
+    max_gain: f32,
+    detector: Detector,
+}
+
+#[derive(Clone, Debug)]
+pub struct Agc {
+    attack: f32,
+    decay: f32,
+    hang: usize,
+    target: f32,
+    max_gain: f32,
+    detector: Detector,
+
+    // in the detector's units
+    level: f32,
+    holding: usize,
+}
+
+impl Default for AgcDesign {
+    fn default() -> Self {
+        AgcDesign {
+            attack: 0.01,
+            decay: 0.5,
+            hang: 0.0,
+            target: 0.5,
+            max_gain: 1000.0,
+            detector: Detector::Peak,
+        }
+    }
+}
+
+impl AgcDesign {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // time constant for a rising level, in seconds
+    pub fn attack(mut self, attack: f32) -> Self {
+        self.attack = attack;
+        self
+    }
+
+    // time constant for a falling level, in seconds
+    pub fn decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    // how long to hold the gain after a peak before decaying, in seconds
+    pub fn hang(mut self, hang: f32) -> Self {
+        self.hang = hang;
+        self
+    }
+
+    // output level, as measured by the detector
+    pub fn target(mut self, target: f32) -> Self {
+        self.target = target;
+        self
+    }
+
+    // linear, not dB. keeps silence from being blown up into noise
+    pub fn max_gain(mut self, max_gain: f32) -> Self {
+        self.max_gain = max_gain;
+        self
+    }
+
+    pub fn detector(mut self, detector: Detector) -> Self {
+        self.detector = detector;
+        self
+    }
+}
+
+impl<A> FilterDesign<A> for AgcDesign where A: Magnitude {
+    type Output = A;
+    type Filter = Agc;
+    fn design(self, rate: f32) -> Self::Filter {
+        Agc {
+            attack: coefficient(self.attack, rate),
+            decay: coefficient(self.decay, rate),
+            hang: (self.hang * rate).round() as usize,
+            target: self.target,
+            max_gain: self.max_gain,
+            detector: self.detector,
+            level: 0.0,
+            holding: 0,
+        }
+    }
+}
+
+impl Agc {
+    // the gain applied to the last sample
+    pub fn gain(&self) -> f32 {
+        let level = match self.detector {
+            Detector::Peak => self.level,
+            Detector::Rms => self.level.sqrt(),
+        };
+        if level * self.max_gain <= self.target {
+            self.max_gain
+        } else {
+            self.target / level
+        }
+    }
+}
+
+impl<A> Filter<A> for Agc where A: Magnitude {
+    type Output = A;
+    fn apply(&mut self, value: A) -> Self::Output {
+        let m = match self.detector {
+            Detector::Peak => value.magnitude(),
+            Detector::Rms => value.magnitude().powi(2),
+        };
+        if m > self.level {
+            self.level += self.attack * (m - self.level);
+            self.holding = self.hang;
+        } else if self.holding > 0 {
+            self.holding -= 1;
+        } else {
+            self.level += self.decay * (m - self.level);
+        }
+        value * self.gain()
+    }
+}
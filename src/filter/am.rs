@@ -0,0 +1,105 @@
+use super::{Filter, FilterDesign};
+use super::pll::{Pll, PllDesign};
+
+use num::Complex;
+
+#[derive(Clone, Debug)]
+pub struct EnvelopeDesign<Dc> {
+    dcfilter: Dc,
+}
+
+#[derive(Clone, Debug)]
+pub struct Envelope<Dc> {
+    dcfilter: Dc,
+}
+
+impl<Dc> EnvelopeDesign<Dc> {
+    // dcfilter should remove the carrier level, e.g. a low highpass
+    pub fn new(dcfilter: Dc) -> Self {
+        EnvelopeDesign {
+            dcfilter,
+        }
+    }
+}
+
+impl<Dc> FilterDesign<Complex<f32>> for EnvelopeDesign<Dc>
+where
+    Dc: FilterDesign<f32, Output=f32>,
+{
+    type Output = f32;
+    type Filter = Envelope<Dc::Filter>;
+    fn design(self, rate: f32) -> Self::Filter {
+        Envelope {
+            dcfilter: self.dcfilter.design(rate),
+        }
+    }
+}
+
+impl<Dc> Filter<Complex<f32>> for Envelope<Dc>
+where
+    Dc: Filter<f32, Output=f32>,
+{
+    type Output = f32;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        self.dcfilter.apply(value.norm())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncAmDesign<Loop, Output, Lock, Dc> {
+    pll: PllDesign<Loop, Output, Lock>,
+    dcfilter: Dc,
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncAm<Loop, Output, Lock, Dc> {
+    pub pll: Pll<Loop, Output, Lock>,
+    dcfilter: Dc,
+}
+
+impl<Loop, Output, Lock, Dc> SyncAmDesign<Loop, Output, Lock, Dc> {
+    // the pll locks to the carrier, so its output filter is unused
+    pub fn new(pll: PllDesign<Loop, Output, Lock>, dcfilter: Dc) -> Self {
+        SyncAmDesign {
+            pll,
+            dcfilter,
+        }
+    }
+}
+
+impl<Loop, Output, Lock, Dc> FilterDesign<Complex<f32>>
+    for SyncAmDesign<Loop, Output, Lock, Dc>
+where
+    Loop: FilterDesign<Complex<f32>, Output=Complex<f32>>,
+    Output: FilterDesign<f32, Output=f32>,
+    Lock: FilterDesign<f32, Output=f32>,
+    Dc: FilterDesign<f32, Output=f32>,
+{
+    type Output = Option<f32>;
+    type Filter = SyncAm<Loop::Filter, Output::Filter, Lock::Filter, Dc::Filter>;
+    fn design(self, rate: f32) -> Self::Filter {
+        SyncAm {
+            pll: self.pll.design(rate),
+            dcfilter: self.dcfilter.design(rate),
+        }
+    }
+}
+
+impl<Loop, Output, Lock, Dc> Filter<Complex<f32>>
+    for SyncAm<Loop, Output, Lock, Dc>
+where
+    Loop: Filter<Complex<f32>, Output=Complex<f32>>,
+    Output: Filter<f32, Output=f32>,
+    Lock: Filter<f32, Output=f32>,
+    Dc: Filter<f32, Output=f32>,
+{
+    type Output = Option<f32>;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        // in-phase component, relative to the carrier estimate
+        // the pll had *before* it saw this sample
+        let inphase = (value * self.pll.value.conj()).re;
+        let locked = self.pll.apply(value);
+        let output = self.dcfilter.apply(inphase);
+        locked.map(|_| output)
+    }
+}
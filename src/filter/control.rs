@@ -0,0 +1,51 @@
+use std::f32::consts::PI;
+
+// a second-order loop, as in every PLL, Costas loop and FLL. phase and
+// frequency are in radians per sample, the error in whatever the
+// detector produces, ideally about -1 to 1
+#[derive(Clone, Debug)]
+pub struct ControlLoop {
+    alpha: f32,
+    beta: f32,
+    max_frequency: f32,
+    pub phase: f32,
+    pub frequency: f32,
+}
+
+impl ControlLoop {
+    // bandwidth in Hz sets how fast the loop follows, and how much noise
+    // gets through. damping of 1/sqrt(2) is a good default, 1 is
+    // critically damped
+    pub fn new(bandwidth: f32, damping: f32, rate: f32) -> Self {
+        let omega = 2.0 * PI * bandwidth / rate;
+        let denom = 1.0 + 2.0 * damping * omega + omega * omega;
+        ControlLoop {
+            alpha: 4.0 * damping * omega / denom,
+            beta: 4.0 * omega * omega / denom,
+            max_frequency: PI,
+            phase: 0.0,
+            frequency: 0.0,
+        }
+    }
+
+    // in radians per sample, to keep the loop from running away
+    pub fn limit(mut self, max_frequency: f32) -> Self {
+        self.max_frequency = max_frequency;
+        self
+    }
+
+    pub fn advance(&mut self, error: f32) {
+        self.frequency += self.beta * error;
+        self.frequency = self.frequency.max(-self.max_frequency)
+            .min(self.max_frequency);
+        self.phase += self.frequency + self.alpha * error;
+        // keep it small, for precision
+        if self.phase > PI || self.phase < -PI {
+            self.phase -= 2.0 * PI * (self.phase / (2.0 * PI)).round();
+        }
+    }
+
+    pub fn oscillator(&self) -> num::Complex<f32> {
+        num::Complex::from_polar(&1.0, &self.phase)
+    }
+}
@@ -0,0 +1,210 @@
+use super::{Filter, FilterDesign};
+use super::{ControlLoop, Fir};
+
+use num::Complex;
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Psk {
+    Bpsk,
+    Qpsk,
+    Psk8,
+}
+
+fn sign(x: f32) -> f32 {
+    if x >= 0.0 { 1.0 } else { -1.0 }
+}
+
+impl Psk {
+    pub fn order(&self) -> usize {
+        match self {
+            Psk::Bpsk => 2,
+            Psk::Qpsk => 4,
+            Psk::Psk8 => 8,
+        }
+    }
+
+    // phase error of a derotated symbol, positive if it leads. BPSK and
+    // 8PSK have a point at 1, QPSK has them on the diagonals
+    pub fn phase_error(&self, v: Complex<f32>) -> f32 {
+        let error = match self {
+            Psk::Bpsk => v.re * v.im,
+            Psk::Qpsk => sign(v.re) * v.im - sign(v.im) * v.re,
+            Psk::Psk8 => {
+                // this detector settles halfway between the axes
+                let v = v * Complex::from_polar(&1.0, &(PI / 8.0));
+                let k = std::f32::consts::SQRT_2 - 1.0;
+                if v.re.abs() >= v.im.abs() {
+                    sign(v.re) * v.im - k * sign(v.im) * v.re
+                } else {
+                    k * sign(v.re) * v.im - sign(v.im) * v.re
+                }
+            },
+        };
+        error.max(-1.0).min(1.0)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CostasDesign {
+    psk: Psk,
+    bandwidth: f32,
+    damping: f32,
+}
+
+// carrier recovery for PSK. expects symbols near unit magnitude, so put
+// an AGC first. outputs the derotated input
+#[derive(Clone, Debug)]
+pub struct Costas {
+    psk: Psk,
+    rate: f32,
+    pub control: ControlLoop,
+}
+
+impl CostasDesign {
+    // loop bandwidth in Hz. a few percent of the symbol rate is typical
+    pub fn new(psk: Psk, bandwidth: f32) -> Self {
+        CostasDesign {
+            psk,
+            bandwidth,
+            damping: std::f32::consts::FRAC_1_SQRT_2,
+        }
+    }
+
+    pub fn damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+}
+
+impl FilterDesign<Complex<f32>> for CostasDesign {
+    type Output = Complex<f32>;
+    type Filter = Costas;
+    fn design(self, rate: f32) -> Self::Filter {
+        Costas {
+            psk: self.psk,
+            rate,
+            // past this, a neighboring symbol looks just as good
+            control: ControlLoop::new(self.bandwidth, self.damping, rate)
+                .limit(PI / self.psk.order() as f32),
+        }
+    }
+}
+
+impl Costas {
+    // carrier offset, in Hz
+    pub fn frequency(&self) -> f32 {
+        self.control.frequency * self.rate / (2.0 * PI)
+    }
+}
+
+impl Filter<Complex<f32>> for Costas {
+    type Output = Complex<f32>;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        let out = value * self.control.oscillator().conj();
+        self.control.advance(self.psk.phase_error(out));
+        out
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BandEdgeDesign {
+    sps: f32,
+    rolloff: f32,
+    taps: usize,
+    bandwidth: f32,
+    damping: f32,
+}
+
+// frequency-locked loop, balancing the energy at the two band edges of
+// a root raised cosine signal. coarse, but locks from far away, so it
+// goes in front of a Costas loop
+#[derive(Clone, Debug)]
+pub struct BandEdge {
+    rate: f32,
+    upper: Fir<Complex<f32>, Complex<f32>>,
+    lower: Fir<Complex<f32>, Complex<f32>>,
+    pub control: ControlLoop,
+}
+
+impl BandEdgeDesign {
+    // samples per symbol, rolloff of the pulse shape, and loop bandwidth
+    // in Hz
+    pub fn new(sps: f32, rolloff: f32, bandwidth: f32) -> Self {
+        BandEdgeDesign {
+            sps,
+            rolloff,
+            taps: (4.0 * sps).round() as usize * 2 + 1,
+            bandwidth,
+            damping: std::f32::consts::FRAC_1_SQRT_2,
+        }
+    }
+
+    pub fn taps(mut self, taps: usize) -> Self {
+        self.taps = taps | 1;
+        self
+    }
+
+    pub fn damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    // the band edge filters, upper then lower
+    fn filters(&self) -> (Vec<Complex<f32>>, Vec<Complex<f32>>) {
+        let sinc = |x: f32| {
+            if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) }
+        };
+        let n = self.taps;
+        let half = (n / 2) as f32;
+
+        // the shape of one band edge, at baseband
+        let base: Vec<f32> = (0..n).map(|i| {
+            let k = (i as f32 - half) * 2.0 / self.sps;
+            sinc(self.rolloff * k - 0.5) + sinc(self.rolloff * k + 0.5)
+        }).collect();
+        let power: f32 = base.iter().sum();
+
+        // then moved up and down to the edges
+        let edge = (1.0 + self.rolloff) / (2.0 * self.sps);
+        let spin = |sign: f32| base.iter().enumerate().map(|(i, b)| {
+            let phase = sign * 2.0 * PI * edge * (i as f32 - half);
+            Complex::from_polar(&(b / power), &phase)
+        }).collect();
+        (spin(1.0), spin(-1.0))
+    }
+}
+
+impl FilterDesign<Complex<f32>> for BandEdgeDesign {
+    type Output = Complex<f32>;
+    type Filter = BandEdge;
+    fn design(self, rate: f32) -> Self::Filter {
+        let (upper, lower) = self.filters();
+        BandEdge {
+            rate,
+            upper: Fir::new(upper),
+            lower: Fir::new(lower),
+            control: ControlLoop::new(self.bandwidth, self.damping, rate)
+                .limit(2.0 * PI / self.sps),
+        }
+    }
+}
+
+impl BandEdge {
+    // carrier offset, in Hz
+    pub fn frequency(&self) -> f32 {
+        self.control.frequency * self.rate / (2.0 * PI)
+    }
+}
+
+impl Filter<Complex<f32>> for BandEdge {
+    type Output = Complex<f32>;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        let out = value * self.control.oscillator().conj();
+        let upper = self.upper.apply(out).norm_sqr();
+        let lower = self.lower.apply(out).norm_sqr();
+        // too high in frequency means more energy in the upper edge
+        self.control.advance(upper - lower);
+        out
+    }
+}
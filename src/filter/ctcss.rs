@@ -0,0 +1,138 @@
+use super::{coefficient, Filter, FilterDesign};
+use super::{Biquad, BiquadD};
+
+use num::Complex;
+
+// standard EIA tones, in Hz
+pub const CTCSS_TONES: &[f32] = &[
+    67.0, 69.3, 71.9, 74.4, 77.0, 79.7, 82.5, 85.4, 88.5, 91.5,
+    94.8, 97.4, 100.0, 103.5, 107.2, 110.9, 114.8, 118.8, 123.0, 127.3,
+    131.8, 136.5, 141.3, 146.2, 150.0, 151.4, 156.7, 159.8, 162.2, 165.5,
+    167.9, 171.3, 173.8, 177.3, 179.9, 183.5, 186.2, 189.9, 192.8, 196.6,
+    199.5, 203.5, 206.5, 210.7, 218.1, 225.7, 229.1, 233.6, 241.8, 250.3,
+    254.1,
+];
+
+// fraction of sub-audible power a tone needs to open, and to stay open
+const OPEN: f32 = 0.3;
+const CLOSE: f32 = 0.15;
+
+#[derive(Clone, Debug)]
+pub struct CtcssDesign {
+    tone: Option<f32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ctcss {
+    tone: Option<f32>,
+    subaudible: (Biquad<f32, f32>, Biquad<f32, f32>),
+    voice: (Biquad<f32, f32>, Biquad<f32, f32>),
+
+    // the tone bank only runs every `every` samples
+    every: usize,
+    i: usize,
+    alpha: f32,
+    // in cycles per bank sample
+    steps: Vec<f32>,
+    nphases: Vec<f32>,
+    levels: Vec<Complex<f32>>,
+    power: f32,
+
+    detected: Option<usize>,
+}
+
+impl CtcssDesign {
+    // with a tone, audio is only passed while that tone is present.
+    // without, audio is always passed and tones are only reported.
+    pub fn new(tone: Option<f32>) -> Self {
+        CtcssDesign {
+            tone,
+        }
+    }
+}
+
+impl FilterDesign<f32> for CtcssDesign {
+    type Output = (f32, Option<f32>);
+    type Filter = Ctcss;
+    fn design(self, rate: f32) -> Self::Filter {
+        // tones are all below 260Hz, voice is all above 300Hz
+        let lowpass = |q| BiquadD::LowPass(260.0, q).design(rate);
+        let highpass = |q| BiquadD::HighPass(300.0, q).design(rate);
+
+        // ~1kHz is plenty for the tone bank
+        let every = ((rate / 1000.0).floor() as usize).max(1);
+        let bankrate = rate / every as f32;
+        let time = 0.25;
+
+        Ctcss {
+            tone: self.tone,
+            subaudible: (lowpass(0.54), lowpass(1.31)),
+            voice: (highpass(0.54), highpass(1.31)),
+
+            every,
+            i: 0,
+            alpha: coefficient(time, bankrate),
+            steps: CTCSS_TONES.iter().map(|f| f / bankrate).collect(),
+            nphases: vec![0.0; CTCSS_TONES.len()],
+            levels: vec![Complex::new(0.0, 0.0); CTCSS_TONES.len()],
+            power: 0.0,
+
+            detected: None,
+        }
+    }
+}
+
+impl Ctcss {
+    pub fn detected(&self) -> Option<f32> {
+        self.detected.map(|i| CTCSS_TONES[i])
+    }
+
+    fn bank(&mut self, value: f32) {
+        use std::f32::consts::PI;
+        for i in 0..CTCSS_TONES.len() {
+            let lo = Complex::from_polar(&1.0, &(-2.0 * PI * self.nphases[i]));
+            let level = self.levels[i];
+            self.levels[i] += (lo * value - level) * self.alpha;
+            self.nphases[i] = (self.nphases[i] + self.steps[i]).fract();
+        }
+        self.power += (value * value - self.power) * self.alpha;
+
+        // a pure tone has power A^2 / 2 and level A / 2
+        let power = self.power.max(f32::MIN_POSITIVE);
+        let fraction = |l: &Complex<f32>| 2.0 * l.norm_sqr() / power;
+        let (best, bestfrac) = self.levels.iter().map(fraction).enumerate()
+            .fold((0, 0.0), |a, b| if b.1 > a.1 { b } else { a });
+
+        if bestfrac > OPEN {
+            self.detected = Some(best);
+        } else if let Some(i) = self.detected {
+            if fraction(&self.levels[i]) < CLOSE {
+                self.detected = None;
+            }
+        }
+    }
+}
+
+impl Filter<f32> for Ctcss {
+    type Output = (f32, Option<f32>);
+    fn apply(&mut self, value: f32) -> Self::Output {
+        let sub = self.subaudible.0.apply(value);
+        let sub = self.subaudible.1.apply(sub);
+        self.i += 1;
+        if self.i >= self.every {
+            self.i = 0;
+            self.bank(sub);
+        }
+
+        let audio = self.voice.0.apply(value);
+        let audio = self.voice.1.apply(audio);
+
+        let detected = self.detected();
+        let open = match (self.tone, detected) {
+            (None, _) => true,
+            (Some(tone), Some(found)) => (tone - found).abs() < 0.5,
+            _ => false,
+        };
+        (if open { audio } else { 0.0 }, detected)
+    }
+}
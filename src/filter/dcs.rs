@@ -0,0 +1,197 @@
+use super::{Filter, FilterDesign};
+use super::{Biquad, BiquadD};
+
+// DCS is a continuously repeating 23 bit golay codeword at 134.4 baud,
+// sent LSB first: 9 bits of code, then 0b100, then 11 bits of parity
+const BAUD: f32 = 134.4;
+
+// x^11 + x^10 + x^6 + x^5 + x^4 + x^2 + 1
+const GOLAY: u32 = 0xc75;
+
+fn parity(data: u32) -> u32 {
+    let mut reg = data << 11;
+    for i in (11..23).rev() {
+        if reg & (1 << i) != 0 {
+            reg ^= GOLAY << (i - 11);
+        }
+    }
+    reg & 0x7ff
+}
+
+fn decode(word: u32) -> Option<u16> {
+    let data = word & 0xfff;
+    if data >> 9 == 0b100 && parity(data) == word >> 12 {
+        Some((data & 0x1ff) as u16)
+    } else {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DcsCode {
+    // as written, in octal: D023N is 0o023
+    pub code: u16,
+    pub inverted: bool,
+}
+
+impl DcsCode {
+    pub fn new(code: u16, inverted: bool) -> Self {
+        DcsCode {
+            code,
+            inverted,
+        }
+    }
+
+    pub fn word(&self) -> u32 {
+        let data = (self.code as u32 & 0x1ff) | 0x800;
+        let word = data | (parity(data) << 12);
+        if self.inverted {
+            !word & 0x7f_ffff
+        } else {
+            word
+        }
+    }
+}
+
+impl std::fmt::Display for DcsCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "D{:03o}{}", self.code, if self.inverted { 'I' } else { 'N' })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DcsDesign {
+    code: Option<DcsCode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dcs {
+    code: Option<DcsCode>,
+    subaudible: (Biquad<f32, f32>, Biquad<f32, f32>),
+    voice: (Biquad<f32, f32>, Biquad<f32, f32>),
+
+    // bit clock, in bits
+    bitstep: f32,
+    bitphase: f32,
+    last: f32,
+
+    register: u32,
+    bits: usize,
+    // a code must be seen twice, one word apart. rotations of a word are
+    // often other valid codes, so there can be a few of these at once.
+    pending: Vec<(DcsCode, usize)>,
+    seen: Vec<(DcsCode, usize)>,
+
+    detected: Option<DcsCode>,
+}
+
+impl DcsDesign {
+    // with a code, audio is only passed while that code is present.
+    // without, audio is always passed and codes are only reported.
+    pub fn new(code: Option<DcsCode>) -> Self {
+        DcsDesign {
+            code,
+        }
+    }
+}
+
+impl FilterDesign<f32> for DcsDesign {
+    type Output = (f32, Option<DcsCode>);
+    type Filter = Dcs;
+    fn design(self, rate: f32) -> Self::Filter {
+        let lowpass = |q| BiquadD::LowPass(300.0, q).design(rate);
+        let highpass = |q| BiquadD::HighPass(300.0, q).design(rate);
+        Dcs {
+            code: self.code,
+            subaudible: (lowpass(0.54), lowpass(1.31)),
+            voice: (highpass(0.54), highpass(1.31)),
+
+            bitstep: BAUD / rate,
+            bitphase: 0.0,
+            last: 0.0,
+
+            register: 0,
+            bits: 0,
+            pending: Vec::new(),
+            seen: Vec::new(),
+
+            detected: None,
+        }
+    }
+}
+
+impl Dcs {
+    pub fn detected(&self) -> Option<DcsCode> {
+        self.detected
+    }
+
+    fn bit(&mut self, bit: bool) {
+        self.register = (self.register >> 1) | ((bit as u32) << 22);
+        self.bits += 1;
+
+        let found = decode(self.register)
+            .map(|c| DcsCode::new(c, false))
+            .or_else(|| decode(!self.register & 0x7f_ffff)
+                     .map(|c| DcsCode::new(c, true)));
+        let bits = self.bits;
+        self.pending.retain(|&(_, at)| bits - at <= 23);
+        // hold on to codes for three words
+        self.seen.retain(|&(_, at)| bits - at <= 3 * 23);
+        if let Some(code) = found {
+            // the same code one word ago. there isn't one in the first
+            // 22 bits, but a partial register can still decode
+            let repeated = bits.checked_sub(23)
+                .map_or(false, |at| self.pending.contains(&(code, at)));
+            if repeated {
+                self.seen.retain(|&(c, _)| c != code);
+                self.seen.push((code, bits));
+            }
+            self.pending.push((code, bits));
+        }
+
+        // prefer the code we're looking for, then the lowest alias
+        let wanted = self.code;
+        self.detected = self.seen.iter().map(|&(c, _)| c)
+            .find(|&c| Some(c) == wanted)
+            .or_else(|| self.seen.iter().map(|&(c, _)| c)
+                     .min_by_key(|c| (c.code, c.inverted)));
+    }
+}
+
+impl Filter<f32> for Dcs {
+    type Output = (f32, Option<DcsCode>);
+    fn apply(&mut self, value: f32) -> Self::Output {
+        let sub = self.subaudible.0.apply(value);
+        let sub = self.subaudible.1.apply(sub);
+
+        // bit boundaries are where the signal crosses zero
+        let start = self.bitphase;
+        self.bitphase += self.bitstep;
+        if (sub > 0.0) != (self.last > 0.0) {
+            let frac = self.last / (self.last - sub);
+            let mut error = (start + frac * self.bitstep).fract();
+            if error > 0.5 {
+                error -= 1.0;
+            }
+            self.bitphase -= 0.1 * error;
+        }
+        self.last = sub;
+
+        // sample in the middle of the bit
+        if start < 0.5 && self.bitphase >= 0.5 {
+            self.bit(sub > 0.0);
+        }
+        if self.bitphase >= 1.0 {
+            self.bitphase -= 1.0;
+        }
+
+        let audio = self.voice.0.apply(value);
+        let audio = self.voice.1.apply(audio);
+
+        let open = match self.code {
+            None => true,
+            Some(code) => self.detected == Some(code),
+        };
+        (if open { audio } else { 0.0 }, self.detected)
+    }
+}
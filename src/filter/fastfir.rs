@@ -0,0 +1,221 @@
+use super::{Filter, FilterDesign};
+use super::fir::Fir;
+use super::convolve::Convolve;
+
+use num::Complex;
+use std::sync::Arc;
+
+// below this many taps, direct convolution wins
+pub const FAST_FIR_THRESHOLD: usize = 64;
+
+// samples that can go through an fft and come back
+pub trait FftSample: Convolve<f32> {
+    fn to_complex(&self) -> Complex<f32>;
+    fn from_complex(c: Complex<f32>) -> Self;
+}
+
+impl FftSample for f32 {
+    fn to_complex(&self) -> Complex<f32> {
+        Complex::new(*self, 0.0)
+    }
+    fn from_complex(c: Complex<f32>) -> Self {
+        c.re
+    }
+}
+
+impl FftSample for Complex<f32> {
+    fn to_complex(&self) -> Complex<f32> {
+        *self
+    }
+    fn from_complex(c: Complex<f32>) -> Self {
+        c
+    }
+}
+
+// block fir, via fft overlap-save. output matches Fir, but delayed by
+// latency() samples
+#[derive(Clone)]
+pub struct FastFir<A> {
+    taps: usize,
+    // tap spectrum, with the 1/n of the inverse fft folded in
+    spectrum: Vec<Complex<f32>>,
+    forward: Arc<dyn rustfft::FFT<f32>>,
+    inverse: Arc<dyn rustfft::FFT<f32>>,
+
+    // last taps - 1 samples of the previous block, then this block
+    input: Vec<Complex<f32>>,
+    // fft buffers, so process() never allocates
+    block: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    output: Vec<A>,
+    pos: usize,
+}
+
+impl<A> FastFir<A> where A: FftSample {
+    pub fn new(coef: Vec<f32>) -> Self {
+        let taps = coef.len().max(1);
+        let size = (2 * taps).next_power_of_two();
+
+        let forward = rustfft::FFTplanner::new(false).plan_fft(size);
+        let inverse = rustfft::FFTplanner::new(true).plan_fft(size);
+
+        let norm = 1.0 / size as f32;
+        let mut padded: Vec<Complex<f32>> = coef.iter()
+            .map(|&c| Complex::new(c * norm, 0.0)).collect();
+        padded.resize(size, Complex::new(0.0, 0.0));
+        let mut spectrum = vec![Complex::new(0.0, 0.0); size];
+        forward.process(&mut padded, &mut spectrum);
+
+        FastFir {
+            taps,
+            spectrum,
+            forward,
+            inverse,
+            input: vec![Complex::new(0.0, 0.0); size],
+            block: vec![Complex::new(0.0, 0.0); size],
+            scratch: vec![Complex::new(0.0, 0.0); size],
+            output: vec![A::zero(); size - taps + 1],
+            pos: 0,
+        }
+    }
+
+    // new samples per fft, which is also the delay relative to Fir
+    pub fn latency(&self) -> usize {
+        self.output.len()
+    }
+
+    fn process(&mut self) {
+        let size = self.input.len();
+        let history = self.taps - 1;
+
+        // the fft scribbles on its input, and we still need the history
+        self.block.copy_from_slice(&self.input);
+        self.forward.process(&mut self.block, &mut self.scratch);
+        for (s, h) in self.scratch.iter_mut().zip(self.spectrum.iter()) {
+            *s *= h;
+        }
+        self.inverse.process(&mut self.scratch, &mut self.block);
+
+        // the first taps - 1 outputs wrapped around, and are garbage
+        let valid = self.block[history..].iter();
+        for (o, v) in self.output.iter_mut().zip(valid) {
+            *o = A::from_complex(*v);
+        }
+        self.input.copy_within(size - history.., 0);
+    }
+}
+
+impl<A> std::fmt::Debug for FastFir<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FastFir")
+            .field("taps", &self.taps)
+            .field("size", &self.input.len())
+            .finish()
+    }
+}
+
+impl<A> Filter<A> for FastFir<A> where A: FftSample {
+    type Output = A;
+    fn apply(&mut self, value: A) -> Self::Output {
+        let out = std::mem::replace(&mut self.output[self.pos], A::zero());
+        self.input[self.taps - 1 + self.pos] = value.to_complex();
+        self.pos += 1;
+        if self.pos == self.output.len() {
+            self.process();
+            self.pos = 0;
+        }
+        out
+    }
+}
+
+// either engine, picked by tap count
+#[derive(Clone, Debug)]
+pub enum AutoFir<A> {
+    Direct(Fir<f32, A>),
+    Fast(FastFir<A>),
+}
+
+impl<A> AutoFir<A> where A: FftSample {
+    pub fn new(coef: Vec<f32>) -> Self {
+        if coef.len() >= FAST_FIR_THRESHOLD {
+            AutoFir::Fast(FastFir::new(coef))
+        } else {
+            AutoFir::Direct(Fir::new(coef))
+        }
+    }
+
+    pub fn latency(&self) -> usize {
+        match self {
+            AutoFir::Direct(_) => 0,
+            AutoFir::Fast(f) => f.latency(),
+        }
+    }
+}
+
+impl<A> Filter<A> for AutoFir<A> where A: FftSample {
+    type Output = A;
+    fn apply(&mut self, value: A) -> Self::Output {
+        match self {
+            AutoFir::Direct(f) => f.apply(value),
+            AutoFir::Fast(f) => f.apply(value),
+        }
+    }
+}
+
+// wraps any fir design, like FirD or Equiripple, to pick the engine
+#[derive(Clone, Debug)]
+pub struct AutoFirD<D>(pub D);
+
+impl<A, D> FilterDesign<A> for AutoFirD<D>
+where
+    A: FftSample,
+    D: FilterDesign<A, Output=A, Filter=Fir<f32, A>>,
+{
+    type Output = A;
+    type Filter = AutoFir<A>;
+    fn design(self, rate: f32) -> Self::Filter {
+        let fir = self.0.design(rate);
+        AutoFir::new(fir.coefficients().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the largest difference from Fir, once lined up by latency()
+    fn worst<A>(taps: usize, input: &[A]) -> f32
+    where
+        A: FftSample + Copy + std::ops::Sub<Output=A>,
+    {
+        let coef: Vec<f32> = (0..taps)
+            .map(|i| ((i * 37 % 17) as f32 - 8.0) / (8.0 * taps as f32))
+            .collect();
+        let mut direct = Fir::new(coef.clone());
+        let mut fast = FastFir::new(coef);
+        let latency = fast.latency();
+        let direct: Vec<A> = input.iter().map(|&x| direct.apply(x)).collect();
+        let fast: Vec<A> = input.iter().map(|&x| fast.apply(x)).collect();
+        assert!(input.len() > 3 * latency);
+        direct.iter().zip(fast[latency..].iter())
+            .map(|(&d, &f)| (d - f).to_complex().norm())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn matches_fir() {
+        // 63 taps fill a 128 point fft short of one block, 64 and 65 fill
+        // it to and past it, and 1 is a plain gain
+        for &taps in &[1, 2, 63, 64, 65, 127, 128, 129, 200] {
+            let real: Vec<f32> = (0..2000)
+                .map(|i| (i * 7919 % 101) as f32 / 50.0 - 1.0)
+                .collect();
+            let complex: Vec<Complex<f32>> = real.iter().enumerate()
+                .map(|(i, &x)| Complex::new(x, (i as f32 * 0.3).sin()))
+                .collect();
+            let (r, c) = (worst(taps, &real), worst(taps, &complex));
+            assert!(r < 1e-4, "{} taps, real, off by {}", taps, r);
+            assert!(c < 1e-4, "{} taps, complex, off by {}", taps, c);
+        }
+    }
+}
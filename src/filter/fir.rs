@@ -16,6 +16,10 @@ impl<C, A> Fir<C, A> where A: Convolve<C> {
             coef,
         }
     }
+
+    pub fn coefficients(&self) -> &[C] {
+        &self.coef
+    }
 }
 
 impl<C, A> Filter<A> for Fir<C, A> where A: Convolve<C> {
@@ -56,3 +60,153 @@ impl<'a, C, A> FilterDesign<A> for &'a [C] where A: Convolve<C>, C: Clone {
         Fir::new(self.to_owned())
     }
 }
+
+#[derive(Clone, Copy, Debug)]
+pub enum Window {
+    // tap count
+    Hamming(usize),
+    Blackman(usize),
+    BlackmanHarris(usize),
+
+    // transition width in Hz, centered on the cutoff, and stopband
+    // attenuation in dB. the tap count follows from these
+    Kaiser(f32, f32),
+}
+
+// modified bessel function of the first kind, order 0
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let halfx = x / 2.0;
+    for k in 1..50 {
+        term *= halfx / k as f32;
+        sum += term * term;
+        if term * term < sum * 1e-9 {
+            break;
+        }
+    }
+    sum
+}
+
+impl Window {
+    // always odd, so there's a center tap and high-pass works
+    pub fn len(&self, rate: f32) -> usize {
+        use Window::*;
+        let n = match *self {
+            Hamming(n) | Blackman(n) | BlackmanHarris(n) => n,
+            Kaiser(width, atten) => {
+                // a zero width would need infinitely many taps
+                assert!(width > 0.0, "kaiser transition width must be \
+                                      positive, got {} Hz", width);
+                let n = (atten - 7.95) / (14.36 * width / rate);
+                n.ceil().max(0.0) as usize + 1
+            },
+        };
+        n.max(1) | 1
+    }
+
+    pub fn coefficients(&self, rate: f32) -> Vec<f32> {
+        use Window::*;
+        use std::f32::consts::PI;
+        let n = self.len(rate);
+        if n == 1 {
+            return vec![1.0];
+        }
+        let cos = |i: usize, k: f32| (k * 2.0 * PI * i as f32 / (n - 1) as f32).cos();
+        (0..n).map(|i| match *self {
+            Hamming(_) => 0.54 - 0.46 * cos(i, 1.0),
+            Blackman(_) => 0.42 - 0.5 * cos(i, 1.0) + 0.08 * cos(i, 2.0),
+            BlackmanHarris(_) => 0.35875 - 0.48829 * cos(i, 1.0)
+                + 0.14128 * cos(i, 2.0) - 0.01168 * cos(i, 3.0),
+            Kaiser(_, atten) => {
+                let beta = if atten > 50.0 {
+                    0.1102 * (atten - 8.7)
+                } else if atten >= 21.0 {
+                    0.5842 * (atten - 21.0).powf(0.4) + 0.07886 * (atten - 21.0)
+                } else {
+                    0.0
+                };
+                let x = 2.0 * i as f32 / (n - 1) as f32 - 1.0;
+                bessel_i0(beta * (1.0 - x * x).max(0.0).sqrt()) / bessel_i0(beta)
+            },
+        }).collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum FirD {
+    LowPass(f32, Window),
+    HighPass(f32, Window),
+    BandPass(f32, f32, Window),
+    BandStop(f32, f32, Window),
+}
+
+impl FirD {
+    pub fn window(&self) -> Window {
+        use FirD::*;
+        match *self {
+            LowPass(_, w) | HighPass(_, w) => w,
+            BandPass(_, _, w) | BandStop(_, _, w) => w,
+        }
+    }
+
+    pub fn taps(&self, rate: f32) -> Vec<f32> {
+        use FirD::*;
+        use std::f32::consts::PI;
+        let window = self.window().coefficients(rate);
+        let n = window.len();
+        let center = (n / 2) as isize;
+
+        // ideal low-pass, windowed, with unity gain at DC. at 0 Hz it
+        // passes nothing, and there's no gain to normalize
+        let lowpass = |freq: f32| {
+            if freq <= 0.0 {
+                return vec![0.0; n];
+            }
+            let fc = freq / rate;
+            let mut h: Vec<f32> = window.iter().enumerate().map(|(i, w)| {
+                let x = (i as isize - center) as f32;
+                let sinc = if x == 0.0 {
+                    2.0 * fc
+                } else {
+                    (2.0 * PI * fc * x).sin() / (PI * x)
+                };
+                sinc * w
+            }).collect();
+            let sum: f32 = h.iter().sum();
+            for v in h.iter_mut() {
+                *v /= sum;
+            }
+            h
+        };
+        // subtract from a delta, the center tap
+        let invert = |mut h: Vec<f32>| {
+            for v in h.iter_mut() {
+                *v = -*v;
+            }
+            h[center as usize] += 1.0;
+            h
+        };
+
+        match *self {
+            LowPass(freq, _) => lowpass(freq),
+            HighPass(freq, _) => invert(lowpass(freq)),
+            BandPass(lo, hi, _) => {
+                lowpass(hi).iter().zip(lowpass(lo).iter())
+                    .map(|(a, b)| a - b).collect()
+            },
+            BandStop(lo, hi, _) => {
+                invert(lowpass(hi).iter().zip(lowpass(lo).iter())
+                       .map(|(a, b)| a - b).collect())
+            },
+        }
+    }
+}
+
+impl<A> FilterDesign<A> for FirD where A: Convolve<f32> {
+    type Output = A;
+    type Filter = Fir<f32, A>;
+    fn design(self, rate: f32) -> Self::Filter {
+        Fir::new(self.taps(rate))
+    }
+}
@@ -0,0 +1,416 @@
+use super::{Filter, FilterDesign};
+use super::biquad::Biquad;
+use super::convolve::Convolve;
+
+use num::Complex;
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+type C64 = Complex<f64>;
+
+#[derive(Clone, Debug)]
+pub struct BiquadCascade<C, A> {
+    stages: Vec<Biquad<C, A>>,
+}
+
+impl<C, A> BiquadCascade<C, A> {
+    pub fn new(stages: Vec<Biquad<C, A>>) -> Self {
+        BiquadCascade {
+            stages,
+        }
+    }
+
+    pub fn stages(&self) -> &[Biquad<C, A>] {
+        &self.stages
+    }
+}
+
+impl<C, A> Filter<A> for BiquadCascade<C, A> where A: Convolve<C> {
+    type Output = A;
+    fn apply(&mut self, value: A) -> Self::Output {
+        self.stages.iter_mut().fold(value, |v, s| s.apply(v))
+    }
+}
+
+impl<C, A> FilterDesign<A> for BiquadCascade<C, A> where A: Convolve<C> {
+    type Output = A;
+    type Filter = BiquadCascade<C, A>;
+    fn design(self, rate: f32) -> Self::Filter {
+        BiquadCascade {
+            stages: self.stages.into_iter().map(|s| s.design(rate)).collect(),
+        }
+    }
+}
+
+// analog low-pass prototypes, by order
+#[derive(Clone, Copy, Debug)]
+pub enum Prototype {
+    Butterworth(usize),
+    // passband ripple in dB. cutoff is the edge of the passband
+    Chebyshev1(usize, f32),
+    // stopband attenuation in dB. cutoff is the edge of the stopband
+    Chebyshev2(usize, f32),
+    // passband ripple and stopband attenuation in dB. cutoff is the edge
+    // of the passband
+    Elliptic(usize, f32, f32),
+}
+
+// poles, zeros, and gain
+#[derive(Clone, Debug)]
+struct Zpk {
+    zeros: Vec<C64>,
+    poles: Vec<C64>,
+    gain: f64,
+}
+
+fn prod(v: &[C64]) -> C64 {
+    v.iter().fold(C64::new(1.0, 0.0), |a, b| a * b)
+}
+
+fn pad(v: &mut Vec<C64>, root: C64, count: usize) {
+    v.extend(std::iter::repeat(root).take(count));
+}
+
+fn neg(v: &[C64]) -> Vec<C64> {
+    v.iter().map(|x| -x).collect()
+}
+
+// descending landen moduli, for the elliptic functions
+fn landen(mut k: f64) -> Vec<f64> {
+    let mut v = vec![];
+    if k == 0.0 || k == 1.0 {
+        return vec![k];
+    }
+    for _ in 0..7 {
+        k = (k / (1.0 + (1.0 - k * k).sqrt())).powi(2);
+        v.push(k);
+    }
+    v
+}
+
+// cd(uK, k) and sn(uK, k), via landen
+fn cde(u: C64, k: f64) -> C64 {
+    let mut w = (u * PI / 2.0).cos();
+    for v in landen(k).iter().rev() {
+        w = (1.0 + v) * w / (1.0 + v * w * w);
+    }
+    w
+}
+
+fn sne(u: C64, k: f64) -> C64 {
+    let mut w = (u * PI / 2.0).sin();
+    for v in landen(k).iter().rev() {
+        w = (1.0 + v) * w / (1.0 + v * w * w);
+    }
+    w
+}
+
+// inverse of sne
+fn asne(mut w: C64, k: f64) -> C64 {
+    let mut last = k;
+    for v in landen(k) {
+        w = w / (1.0 + (1.0 - w * w * last * last).sqrt()) * 2.0 / (1.0 + v);
+        last = v;
+    }
+    w.asin() * 2.0 / PI
+}
+
+impl Prototype {
+    pub fn order(&self) -> usize {
+        use Prototype::*;
+        match *self {
+            Butterworth(n) | Chebyshev1(n, _) | Chebyshev2(n, _) => n,
+            Elliptic(n, _, _) => n,
+        }
+    }
+
+    // normalized to 1 rad/s
+    fn zpk(&self) -> Zpk {
+        use Prototype::*;
+        let n = self.order().max(1);
+        // m in -n+1, -n+3, ..., n-1
+        let ms = (0..n).map(|i| (2 * i) as f64 - (n - 1) as f64);
+        match *self {
+            Butterworth(_) => {
+                let poles = ms
+                    .map(|m| -C64::new(0.0, PI * m / (2 * n) as f64).exp())
+                    .collect();
+                Zpk { zeros: vec![], poles, gain: 1.0 }
+            },
+            Chebyshev1(_, ripple) => {
+                let eps = (10f64.powf(0.1 * ripple as f64) - 1.0).sqrt();
+                let mu = (1.0 / eps).asinh() / n as f64;
+                let poles: Vec<C64> = ms.map(|m| {
+                    let theta = PI * m / (2 * n) as f64;
+                    -C64::new(mu, theta).sinh()
+                }).collect();
+                let mut gain = prod(&neg(&poles)).re;
+                if n % 2 == 0 {
+                    gain /= (1.0 + eps * eps).sqrt();
+                }
+                Zpk { zeros: vec![], poles, gain }
+            },
+            Chebyshev2(_, atten) => {
+                let de = 1.0 / (10f64.powf(0.1 * atten as f64) - 1.0).sqrt();
+                let mu = (1.0 / de).asinh() / n as f64;
+                let zeros: Vec<C64> = ms.clone().filter(|&m| m != 0.0)
+                    .map(|m| {
+                        let s = (m * PI / (2 * n) as f64).sin();
+                        -(C64::new(0.0, 1.0) / s).conj()
+                    }).collect();
+                let poles: Vec<C64> = ms.map(|m| {
+                    let p = -C64::new(0.0, PI * m / (2 * n) as f64).exp();
+                    1.0 / C64::new(mu.sinh() * p.re, mu.cosh() * p.im)
+                }).collect();
+                let gain = (prod(&neg(&poles)) / prod(&neg(&zeros))).re;
+                Zpk { zeros, poles, gain }
+            },
+            Elliptic(_, ripple, atten) => {
+                // after Orfanidis, "Lecture Notes on Elliptic Filter Design"
+                let ep = (10f64.powf(0.1 * ripple as f64) - 1.0).sqrt();
+                let es = (10f64.powf(0.1 * atten as f64) - 1.0).sqrt();
+                let k1 = ep / es;
+                let k1p = (1.0 - k1 * k1).sqrt();
+                let l = n / 2;
+                let us: Vec<f64> = (1..=l)
+                    .map(|i| (2 * i - 1) as f64 / n as f64).collect();
+
+                // solve the degree equation for the selectivity k
+                let kp = k1p.powi(n as i32) * us.iter()
+                    .map(|&u| sne(C64::new(u, 0.0), k1p).re.powi(4))
+                    .product::<f64>();
+                let k = (1.0 - kp * kp).sqrt();
+
+                let j = C64::new(0.0, 1.0);
+                let v0 = (-j * asne(j / ep, k1) / n as f64).re;
+
+                let mut zeros = vec![];
+                let mut poles = vec![];
+                for &u in us.iter() {
+                    let z = j / (k * cde(C64::new(u, 0.0), k));
+                    let p = j * cde(C64::new(u, -v0), k);
+                    zeros.push(z);
+                    zeros.push(z.conj());
+                    poles.push(p);
+                    poles.push(p.conj());
+                }
+                if n % 2 == 1 {
+                    poles.push(j * sne(C64::new(0.0, v0), k));
+                }
+
+                let mut gain = (prod(&neg(&poles)) / prod(&neg(&zeros))).re;
+                if n % 2 == 0 {
+                    gain /= (1.0 + ep * ep).sqrt();
+                }
+                Zpk { zeros, poles, gain }
+            },
+        }
+    }
+}
+
+impl Zpk {
+    fn degree(&self) -> usize {
+        self.poles.len() - self.zeros.len()
+    }
+
+    fn lowpass(self, wo: f64) -> Zpk {
+        let degree = self.degree() as i32;
+        Zpk {
+            zeros: self.zeros.iter().map(|z| z * wo).collect(),
+            poles: self.poles.iter().map(|p| p * wo).collect(),
+            gain: self.gain * wo.powi(degree),
+        }
+    }
+
+    fn highpass(self, wo: f64) -> Zpk {
+        let mut zeros: Vec<C64> = self.zeros.iter().map(|z| wo / z).collect();
+        pad(&mut zeros, C64::new(0.0, 0.0), self.degree());
+        Zpk {
+            gain: self.gain
+                * (prod(&neg(&self.zeros)) / prod(&neg(&self.poles))).re,
+            poles: self.poles.iter().map(|p| wo / p).collect(),
+            zeros,
+        }
+    }
+
+    // each root becomes a pair, centered on wo
+    fn bandpass(self, wo: f64, bw: f64) -> Zpk {
+        let split = |v: &[C64]| -> Vec<C64> {
+            v.iter().flat_map(|x| {
+                let x = x * bw / 2.0;
+                let d = (x * x - wo * wo).sqrt();
+                vec![x + d, x - d]
+            }).collect()
+        };
+        let mut zeros = split(&self.zeros);
+        pad(&mut zeros, C64::new(0.0, 0.0), self.degree());
+        Zpk {
+            poles: split(&self.poles),
+            zeros,
+            gain: self.gain * bw.powi(self.degree() as i32),
+        }
+    }
+
+    fn bandstop(self, wo: f64, bw: f64) -> Zpk {
+        let split = |v: &[C64]| -> Vec<C64> {
+            v.iter().flat_map(|x| {
+                let x = bw / 2.0 / x;
+                let d = (x * x - wo * wo).sqrt();
+                vec![x + d, x - d]
+            }).collect()
+        };
+        let mut zeros = split(&self.zeros);
+        for _ in 0..self.degree() {
+            zeros.push(C64::new(0.0, wo));
+            zeros.push(C64::new(0.0, -wo));
+        }
+        Zpk {
+            gain: self.gain
+                * (prod(&neg(&self.zeros)) / prod(&neg(&self.poles))).re,
+            poles: split(&self.poles),
+            zeros,
+        }
+    }
+
+    // analog to digital. extra zeros land on nyquist
+    fn bilinear(self, rate: f64) -> Zpk {
+        let fs2 = 2.0 * rate;
+        let map = |v: &[C64]| -> Vec<C64> {
+            v.iter().map(|x| (fs2 + x) / (fs2 - x)).collect()
+        };
+        let diff = |v: &[C64]| -> Vec<C64> {
+            v.iter().map(|x| fs2 - x).collect()
+        };
+        let mut zeros = map(&self.zeros);
+        pad(&mut zeros, C64::new(-1.0, 0.0), self.degree());
+        Zpk {
+            gain: self.gain
+                * (prod(&diff(&self.zeros)) / prod(&diff(&self.poles))).re,
+            poles: map(&self.poles),
+            zeros,
+        }
+    }
+
+    // digital roots into second-order (or first-order) sections
+    fn sections(self) -> Vec<(Vec<C64>, Vec<C64>)> {
+        let poles = group(&self.poles);
+        let mut zeros = group(&self.zeros);
+
+        // poles nearest the unit circle pick their zeros first.
+        // degenerate designs can leave NaN here, so keep the order total
+        let key = |i: usize| {
+            let r = radius(&poles[i]);
+            if r.is_nan() { 0.0 } else { r }
+        };
+        let mut order: Vec<usize> = (0..poles.len()).collect();
+        order.sort_by(|&a, &b| {
+            key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal)
+        });
+        let mut sections = vec![];
+        for i in order {
+            let p = &poles[i];
+            let nearest = (0..zeros.len()).min_by(|&a, &b| {
+                let da = (zeros[a][0] - p[0]).norm();
+                let db = (zeros[b][0] - p[0]).norm();
+                da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+            });
+            let z = nearest.map(|j| zeros.remove(j)).unwrap_or_default();
+            sections.push((p.clone(), z));
+        }
+
+        // but run the gentlest sections first
+        sections.reverse();
+        sections
+    }
+}
+
+fn radius(v: &[C64]) -> f64 {
+    v.iter().map(|x| x.norm()).fold(0.0, f64::max)
+}
+
+// conjugate pairs together, then real roots two at a time
+fn group(roots: &[C64]) -> Vec<Vec<C64>> {
+    let eps = 1e-9;
+    let mut groups = vec![];
+    let mut reals = vec![];
+    for r in roots {
+        if r.im > eps {
+            groups.push(vec![*r, r.conj()]);
+        } else if r.im.abs() <= eps {
+            reals.push(C64::new(r.re, 0.0));
+        }
+    }
+    for pair in reals.chunks(2) {
+        groups.push(pair.to_vec());
+    }
+    groups
+}
+
+// polynomial coefficients in z^-1, from up to two roots
+fn poly(roots: &[C64]) -> [f64; 3] {
+    match roots.len() {
+        0 => [1.0, 0.0, 0.0],
+        1 => [1.0, -roots[0].re, 0.0],
+        _ => [1.0, -(roots[0] + roots[1]).re, (roots[0] * roots[1]).re],
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum IirD {
+    // in Hz
+    LowPass(f32, Prototype),
+    HighPass(f32, Prototype),
+    // lower and upper edge. these have twice the prototype's order
+    BandPass(f32, f32, Prototype),
+    BandStop(f32, f32, Prototype),
+}
+
+impl IirD {
+    fn zpk(&self, rate: f32) -> Zpk {
+        use IirD::*;
+        let rate = rate as f64;
+        // pre-warp, so the edges land where they were asked for
+        let warp = |f: f32| 2.0 * rate * (PI * f as f64 / rate).tan();
+        let zpk = match *self {
+            LowPass(f, p) => p.zpk().lowpass(warp(f)),
+            HighPass(f, p) => p.zpk().highpass(warp(f)),
+            BandPass(lo, hi, p) => {
+                let (lo, hi) = (warp(lo), warp(hi));
+                p.zpk().bandpass((lo * hi).sqrt(), hi - lo)
+            },
+            BandStop(lo, hi, p) => {
+                let (lo, hi) = (warp(lo), warp(hi));
+                p.zpk().bandstop((lo * hi).sqrt(), hi - lo)
+            },
+        };
+        zpk.bilinear(rate)
+    }
+
+    pub fn stages<A>(&self, rate: f32) -> Vec<Biquad<f32, A>>
+    where
+        A: Convolve<f32>,
+    {
+        let zpk = self.zpk(rate);
+        let gain = zpk.gain;
+        zpk.sections().into_iter().enumerate().map(|(i, (p, z))| {
+            let a = poly(&p);
+            let mut b = poly(&z);
+            // all the gain goes up front
+            if i == 0 {
+                for c in b.iter_mut() {
+                    *c *= gain;
+                }
+            }
+            Biquad::new(a[0] as f32, a[1] as f32, a[2] as f32,
+                        b[0] as f32, b[1] as f32, b[2] as f32)
+        }).collect()
+    }
+}
+
+impl<A> FilterDesign<A> for IirD where A: Convolve<f32> {
+    type Output = A;
+    type Filter = BiquadCascade<f32, A>;
+    fn design(self, rate: f32) -> Self::Filter {
+        BiquadCascade::new(self.stages(rate))
+    }
+}
@@ -0,0 +1,228 @@
+use super::{coefficient, Filter, FilterDesign};
+
+use num::Complex;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+// two f32 parameters, published by a running filter
+#[derive(Debug, Default)]
+struct Estimate {
+    frozen: AtomicBool,
+    // f32 bits
+    a: AtomicU32,
+    b: AtomicU32,
+}
+
+impl Estimate {
+    fn new(a: f32, b: f32, frozen: bool) -> Arc<Self> {
+        Arc::new(Estimate {
+            frozen: AtomicBool::new(frozen),
+            a: AtomicU32::new(a.to_bits()),
+            b: AtomicU32::new(b.to_bits()),
+        })
+    }
+
+    fn get(&self) -> (f32, f32) {
+        (f32::from_bits(self.a.load(Ordering::Relaxed)),
+         f32::from_bits(self.b.load(Ordering::Relaxed)))
+    }
+
+    fn set(&self, a: f32, b: f32) {
+        self.a.store(a.to_bits(), Ordering::Relaxed);
+        self.b.store(b.to_bits(), Ordering::Relaxed);
+    }
+
+    fn frozen(&self) -> bool {
+        self.frozen.load(Ordering::Relaxed)
+    }
+}
+
+// a window into a running DcBlock, to freeze it and read it back
+#[derive(Clone, Debug)]
+pub struct DcBlockControl {
+    shared: Arc<Estimate>,
+}
+
+impl DcBlockControl {
+    // stop adapting, and keep removing the current offset
+    pub fn freeze(&self, frozen: bool) {
+        self.shared.frozen.store(frozen, Ordering::Relaxed);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.shared.frozen()
+    }
+
+    // the offset being removed, for DcBlockDesign::fixed next time
+    pub fn offset(&self) -> Complex<f32> {
+        let (re, im) = self.shared.get();
+        Complex::new(re, im)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DcBlockDesign {
+    time: f32,
+    control: DcBlockControl,
+}
+
+#[derive(Clone, Debug)]
+pub struct DcBlock {
+    alpha: f32,
+    dc: Complex<f32>,
+    control: DcBlockControl,
+}
+
+impl DcBlockDesign {
+    // time constant of the running mean, in seconds
+    pub fn new(time: f32) -> Self {
+        DcBlockDesign {
+            time,
+            control: DcBlockControl {
+                shared: Estimate::new(0.0, 0.0, false),
+            },
+        }
+    }
+
+    // always removes this offset, until unfrozen
+    pub fn fixed(offset: Complex<f32>) -> Self {
+        DcBlockDesign {
+            time: 0.1,
+            control: DcBlockControl {
+                shared: Estimate::new(offset.re, offset.im, true),
+            },
+        }
+    }
+
+    pub fn control(&self) -> DcBlockControl {
+        self.control.clone()
+    }
+}
+
+impl FilterDesign<Complex<f32>> for DcBlockDesign {
+    type Output = Complex<f32>;
+    type Filter = DcBlock;
+    fn design(self, rate: f32) -> Self::Filter {
+        DcBlock {
+            alpha: coefficient(self.time, rate),
+            dc: self.control.offset(),
+            control: self.control,
+        }
+    }
+}
+
+impl Filter<Complex<f32>> for DcBlock {
+    type Output = Complex<f32>;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        if !self.control.is_frozen() {
+            self.dc += (value - self.dc) * self.alpha;
+            self.control.shared.set(self.dc.re, self.dc.im);
+        }
+        value - self.dc
+    }
+}
+
+// a window into a running IqBalance, to freeze it and read it back
+#[derive(Clone, Debug)]
+pub struct IqBalanceControl {
+    shared: Arc<Estimate>,
+}
+
+impl IqBalanceControl {
+    // stop adapting, and keep applying the current correction
+    pub fn freeze(&self, frozen: bool) {
+        self.shared.frozen.store(frozen, Ordering::Relaxed);
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.shared.frozen()
+    }
+
+    // Q gain relative to I, and Q phase error in radians. for
+    // IqBalanceDesign::fixed next time
+    pub fn imbalance(&self) -> (f32, f32) {
+        self.shared.get()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IqBalanceDesign {
+    time: f32,
+    control: IqBalanceControl,
+}
+
+// models Q as gain * (Q cos phase + I sin phase), and estimates gain and
+// phase from the fact that I and Q should be uncorrelated, with equal
+// power. put a DcBlock first, an offset spoils the statistics
+#[derive(Clone, Debug)]
+pub struct IqBalance {
+    alpha: f32,
+    // running E[I^2], E[Q^2], E[IQ]
+    ii: f32,
+    qq: f32,
+    iq: f32,
+    control: IqBalanceControl,
+}
+
+impl IqBalanceDesign {
+    // time constant of the statistics, in seconds
+    pub fn new(time: f32) -> Self {
+        IqBalanceDesign {
+            time,
+            control: IqBalanceControl {
+                shared: Estimate::new(1.0, 0.0, false),
+            },
+        }
+    }
+
+    // always applies this correction, until unfrozen
+    pub fn fixed(gain: f32, phase: f32) -> Self {
+        IqBalanceDesign {
+            time: 1.0,
+            control: IqBalanceControl {
+                shared: Estimate::new(gain, phase, true),
+            },
+        }
+    }
+
+    pub fn control(&self) -> IqBalanceControl {
+        self.control.clone()
+    }
+}
+
+impl FilterDesign<Complex<f32>> for IqBalanceDesign {
+    type Output = Complex<f32>;
+    type Filter = IqBalance;
+    fn design(self, rate: f32) -> Self::Filter {
+        // start the statistics where the estimate says
+        let (gain, phase) = self.control.imbalance();
+        IqBalance {
+            alpha: coefficient(self.time, rate),
+            ii: 1.0,
+            qq: gain * gain,
+            iq: gain * phase.sin(),
+            control: self.control,
+        }
+    }
+}
+
+impl Filter<Complex<f32>> for IqBalance {
+    type Output = Complex<f32>;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        let (mut gain, mut phase) = self.control.imbalance();
+        if !self.control.is_frozen() {
+            let (i, q) = (value.re, value.im);
+            self.ii += (i * i - self.ii) * self.alpha;
+            self.qq += (q * q - self.qq) * self.alpha;
+            self.iq += (i * q - self.iq) * self.alpha;
+            if self.ii > 0.0 && self.qq > 0.0 {
+                gain = (self.qq / self.ii).sqrt();
+                phase = (self.iq / (self.ii * self.qq).sqrt())
+                    .max(-1.0).min(1.0).asin();
+                self.control.shared.set(gain, phase);
+            }
+        }
+        let q = (value.im / gain - value.re * phase.sin()) / phase.cos();
+        Complex::new(value.re, q)
+    }
+}
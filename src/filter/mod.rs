@@ -9,15 +9,57 @@ pub use simple::*;
 mod fir;
 pub use fir::*;
 
+mod fastfir;
+pub use fastfir::*;
+
+mod remez;
+pub use remez::*;
+
+mod pulse;
+pub use pulse::*;
+
 mod biquad;
 pub use biquad::*;
 
+mod iir;
+pub use iir::*;
+
 mod derivative;
 pub use derivative::*;
 
 mod pll;
 pub use pll::*;
 
+mod control;
+pub use control::*;
+
+mod costas;
+pub use costas::*;
+
+mod agc;
+pub use agc::*;
+
+mod iqcorrect;
+pub use iqcorrect::*;
+
+mod am;
+pub use am::*;
+
+mod ssb;
+pub use ssb::*;
+
+mod wbfm;
+pub use wbfm::*;
+
+mod nbfm;
+pub use nbfm::*;
+
+mod ctcss;
+pub use ctcss::*;
+
+mod dcs;
+pub use dcs::*;
+
 // by rights, this should just be FnMut(A) -> A
 // but... fn_traits is not yet stable (??!)
 pub trait Filter<A> {
@@ -25,6 +67,15 @@ pub trait Filter<A> {
     fn apply(&mut self, value: A) -> Self::Output;
 }
 
+// one-pole smoothing coefficient for a time constant, in seconds
+pub(crate) fn coefficient(time: f32, rate: f32) -> f32 {
+    if time <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-1.0 / (time * rate)).exp()
+    }
+}
+
 pub trait FilterDesign<A>: Sized {
     type Output;
     type Filter: Filter<A, Output=Self::Output>;
@@ -0,0 +1,71 @@
+use super::{Filter, FilterDesign};
+use super::{Biquad, BiquadD};
+
+use num::Complex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NbfmChannel {
+    // 12.5kHz channel spacing
+    Narrow,
+    // 25kHz channel spacing
+    Wide,
+}
+
+impl NbfmChannel {
+    // peak deviation, in Hz
+    pub fn deviation(&self) -> f32 {
+        match self {
+            NbfmChannel::Narrow => 2500.0,
+            NbfmChannel::Wide => 5000.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NbfmDesign {
+    deviation: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct Nbfm {
+    scale: f32,
+    last: Complex<f32>,
+    lowpass: (Biquad<f32, f32>, Biquad<f32, f32>),
+}
+
+impl NbfmDesign {
+    pub fn new(channel: NbfmChannel) -> Self {
+        Self::with_deviation(channel.deviation())
+    }
+
+    pub fn with_deviation(deviation: f32) -> Self {
+        NbfmDesign {
+            deviation,
+        }
+    }
+}
+
+impl FilterDesign<Complex<f32>> for NbfmDesign {
+    type Output = f32;
+    type Filter = Nbfm;
+    fn design(self, rate: f32) -> Self::Filter {
+        // voice only goes up to 3kHz, everything above is noise
+        let lowpass = |q| BiquadD::LowPass(3000.0, q).design(rate);
+        Nbfm {
+            scale: rate / (2.0 * std::f32::consts::PI * self.deviation),
+            last: Complex::new(0.0, 0.0),
+            lowpass: (lowpass(0.54), lowpass(1.31)),
+        }
+    }
+}
+
+impl Filter<Complex<f32>> for Nbfm {
+    type Output = f32;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        // output is normalized so full deviation is 1.0
+        let d = (value * self.last.conj()).arg() * self.scale;
+        self.last = value;
+        let d = self.lowpass.0.apply(d);
+        self.lowpass.1.apply(d)
+    }
+}
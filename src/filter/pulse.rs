@@ -0,0 +1,106 @@
+use super::{Convolve, Fir, FilterDesign};
+
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pulse {
+    // one symbol long, no shaping at all
+    Rectangular,
+    // with rolloff, from 0 to 1
+    RaisedCosine(f32),
+    RootRaisedCosine(f32),
+    // with bandwidth-time product, usually 0.3 or 0.5, for GFSK
+    Gaussian(f32),
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) }
+}
+
+impl Pulse {
+    // the unscaled shape, at a time in symbols from the center
+    pub fn at(&self, t: f32) -> f32 {
+        use Pulse::*;
+        match *self {
+            Rectangular => {
+                if t.abs() < 0.5 || t == -0.5 { 1.0 } else { 0.0 }
+            },
+            RaisedCosine(beta) => {
+                if beta > 0.0 && (2.0 * beta * t).abs() == 1.0 {
+                    PI / 4.0 * sinc(1.0 / (2.0 * beta))
+                } else {
+                    sinc(t) * (PI * beta * t).cos()
+                        / (1.0 - (2.0 * beta * t).powi(2))
+                }
+            },
+            RootRaisedCosine(beta) => {
+                if t == 0.0 {
+                    1.0 - beta + 4.0 * beta / PI
+                } else if beta > 0.0 && (4.0 * beta * t).abs() == 1.0 {
+                    let x = PI / (4.0 * beta);
+                    beta / std::f32::consts::SQRT_2
+                        * ((1.0 + 2.0 / PI) * x.sin()
+                           + (1.0 - 2.0 / PI) * x.cos())
+                } else {
+                    ((PI * t * (1.0 - beta)).sin()
+                     + 4.0 * beta * t * (PI * t * (1.0 + beta)).cos())
+                        / (PI * t * (1.0 - (4.0 * beta * t).powi(2)))
+                }
+            },
+            Gaussian(bt) => {
+                let a = 2.0 * (PI * bt).powi(2) / 2f32.ln();
+                (-a * t * t).exp()
+            },
+        }
+    }
+
+    // what to multiply taps by, sampled at sps. a train of symbols
+    // through a raised cosine or rectangular pulse passes through the
+    // symbols exactly, and two root raised cosines make one. gaussian
+    // pulses have an area of one symbol, so as to shape frequency
+    pub(crate) fn scale(&self, taps: &[f32], sps: f32) -> f32 {
+        use Pulse::*;
+        match *self {
+            Rectangular | Gaussian(_) => {
+                sps / taps.iter().sum::<f32>()
+            },
+            RaisedCosine(_) => {
+                1.0 / taps[taps.len() / 2]
+            },
+            RootRaisedCosine(_) => {
+                1.0 / taps.iter().map(|h| h * h).sum::<f32>().sqrt()
+            },
+        }
+    }
+
+    // half the length, in samples, to cover span symbols
+    pub(crate) fn half(sps: f32, span: usize) -> usize {
+        (span as f32 * sps / 2.0).round() as usize
+    }
+
+    // span in symbols, always an odd number of taps
+    pub fn taps(&self, sps: f32, span: usize) -> Vec<f32> {
+        let half = Pulse::half(sps, span) as isize;
+        let mut taps: Vec<f32> = (-half..=half)
+            .map(|i| self.at(i as f32 / sps)).collect();
+        let scale = self.scale(&taps, sps);
+        for h in taps.iter_mut() {
+            *h *= scale;
+        }
+        taps
+    }
+}
+
+// a pulse as a filter, like a matched filter in front of SymbolSync, with
+// the symbol rate and span in symbols
+#[derive(Clone, Copy, Debug)]
+pub struct PulseD(pub Pulse, pub f32, pub usize);
+
+impl<A> FilterDesign<A> for PulseD where A: Convolve<f32> {
+    type Output = A;
+    type Filter = Fir<f32, A>;
+    fn design(self, rate: f32) -> Self::Filter {
+        let PulseD(pulse, symbol_rate, span) = self;
+        Fir::new(pulse.taps(rate / symbol_rate, span))
+    }
+}
@@ -0,0 +1,270 @@
+use super::FilterDesign;
+use super::fir::Fir;
+use super::convolve::Convolve;
+
+use nalgebra::{DMatrix, DVector};
+
+// grid points per coefficient, over the whole spectrum
+const DENSITY: usize = 16;
+const MAX_ITERATIONS: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct Band {
+    // in Hz
+    pub start: f32,
+    pub end: f32,
+    pub gain: f32,
+    pub weight: f32,
+}
+
+#[derive(Clone, Debug)]
+pub enum RemezError {
+    // bands overlap, are out of order, or go past nyquist
+    BadBands,
+    NoConvergence,
+    Singular,
+}
+
+impl std::fmt::Display for RemezError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RemezError::BadBands => write!(f, "remez: bad band edges"),
+            RemezError::NoConvergence => write!(f, "remez: did not converge"),
+            RemezError::Singular => write!(f, "remez: singular system"),
+        }
+    }
+}
+
+impl std::error::Error for RemezError {}
+
+// equiripple linear-phase design, via the Parks-McClellan algorithm
+#[derive(Clone, Debug)]
+pub struct Remez {
+    taps: usize,
+    bands: Vec<Band>,
+}
+
+// the result of a successful Remez design
+#[derive(Clone, Debug)]
+pub struct Equiripple {
+    pub taps: Vec<f32>,
+    // worst peak-to-peak passband ripple, in dB
+    pub ripple: f32,
+    // worst stopband attenuation, in dB. infinite with no stopbands
+    pub attenuation: f32,
+}
+
+impl Remez {
+    // taps are rounded up to odd, so every band shape is possible
+    pub fn new(taps: usize) -> Self {
+        Remez {
+            taps: taps.max(3) | 1,
+            bands: vec![],
+        }
+    }
+
+    // bands must be added in order, and not overlap
+    pub fn band(mut self, start: f32, end: f32, gain: f32, weight: f32)
+                -> Self
+    {
+        self.bands.push(Band { start, end, gain, weight });
+        self
+    }
+
+    pub fn low_pass(taps: usize, pass: f32, stop: f32, rate: f32) -> Self {
+        Remez::new(taps)
+            .band(0.0, pass, 1.0, 1.0)
+            .band(stop, rate / 2.0, 0.0, 1.0)
+    }
+
+    pub fn solve(&self, rate: f32) -> Result<Equiripple, RemezError> {
+        let nyquist = rate as f64 / 2.0;
+        let mut last = 0.0;
+        for b in self.bands.iter() {
+            let (start, end) = (b.start as f64, b.end as f64);
+            if start < last || end < start || end > nyquist || b.weight <= 0.0 {
+                return Err(RemezError::BadBands);
+            }
+            last = end;
+        }
+        if self.bands.is_empty() {
+            return Err(RemezError::BadBands);
+        }
+
+        // from here on, frequencies are in cycles per sample
+        let m = self.taps / 2;
+        let r = m + 2;
+        let grid = self.grid(rate as f64, m);
+        if grid.len() < r {
+            return Err(RemezError::BadBands);
+        }
+
+        // start with extremals spread evenly over the grid
+        let mut ext: Vec<usize> = (0..r)
+            .map(|i| i * (grid.len() - 1) / (r - 1))
+            .collect();
+
+        for _ in 0..MAX_ITERATIONS {
+            let (coef, delta) = solve_system(&grid, &ext, m)
+                .ok_or(RemezError::Singular)?;
+            let err: Vec<f64> = grid.iter()
+                .map(|p| p.weight * (p.gain - amplitude(&coef, p.freq)))
+                .collect();
+
+            let next = extremals(&grid, &err, r)
+                .ok_or(RemezError::NoConvergence)?;
+            let max = next.iter().map(|&i| err[i].abs())
+                .fold(0.0, f64::max);
+            if next == ext || (max - delta.abs()) <= 1e-6 * max {
+                return Ok(self.finish(&coef, m, rate as f64));
+            }
+            ext = next;
+        }
+        Err(RemezError::NoConvergence)
+    }
+
+    fn grid(&self, rate: f64, m: usize) -> Vec<GridPoint> {
+        let step = 0.5 / (DENSITY * (m + 1)) as f64;
+        let mut grid = vec![];
+        for (band, b) in self.bands.iter().enumerate() {
+            let (start, end) = (b.start as f64 / rate, b.end as f64 / rate);
+            let count = (((end - start) / step).ceil() as usize).max(1);
+            for i in 0..=count {
+                grid.push(GridPoint {
+                    freq: start + (end - start) * i as f64 / count as f64,
+                    gain: b.gain as f64,
+                    weight: b.weight as f64,
+                    band,
+                });
+            }
+        }
+        grid
+    }
+
+    fn finish(&self, coef: &[f64], m: usize, rate: f64) -> Equiripple {
+        let mut taps = vec![0.0; 2 * m + 1];
+        taps[m] = coef[0] as f32;
+        for k in 1..=m {
+            taps[m - k] = (coef[k] / 2.0) as f32;
+            taps[m + k] = (coef[k] / 2.0) as f32;
+        }
+
+        // measure the result band by band, on a finer grid
+        let mut ripple: f32 = 0.0;
+        let mut attenuation = std::f32::INFINITY;
+        for b in self.bands.iter() {
+            let (start, end) = (b.start as f64 / rate, b.end as f64 / rate);
+            let n = 32 * (m + 1);
+            let amps = (0..=n).map(|i| {
+                amplitude(coef, start + (end - start) * i as f64 / n as f64)
+            });
+            if b.gain == 0.0 {
+                let worst = amps.map(f64::abs).fold(0.0, f64::max);
+                attenuation = attenuation.min(-20.0 * worst.log10() as f32);
+            } else {
+                let (lo, hi) = amps.fold((std::f64::INFINITY, 0.0f64),
+                                         |(lo, hi), a| (lo.min(a), hi.max(a)));
+                ripple = ripple.max(20.0 * (hi / lo).log10() as f32);
+            }
+        }
+
+        Equiripple {
+            taps,
+            ripple,
+            attenuation,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GridPoint {
+    freq: f64,
+    gain: f64,
+    weight: f64,
+    band: usize,
+}
+
+// the zero-phase response, sum of a_k cos(2 pi k f)
+fn amplitude(coef: &[f64], freq: f64) -> f64 {
+    let w = 2.0 * std::f64::consts::PI * freq;
+    coef.iter().enumerate().map(|(k, a)| a * (k as f64 * w).cos()).sum()
+}
+
+// find coefficients and delta so the weighted error alternates with
+// equal magnitude on the extremals
+fn solve_system(grid: &[GridPoint], ext: &[usize], m: usize)
+                -> Option<(Vec<f64>, f64)>
+{
+    let r = m + 2;
+    let matrix = DMatrix::<f64>::from_fn(r, r, |i, j| {
+        let p = &grid[ext[i]];
+        if j <= m {
+            (2.0 * std::f64::consts::PI * j as f64 * p.freq).cos()
+        } else {
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            sign / p.weight
+        }
+    });
+    let rhs = DVector::<f64>::from_fn(r, |i, _| grid[ext[i]].gain);
+    let solution = matrix.lu().solve(&rhs)?;
+    let coef = solution.iter().take(m + 1).cloned().collect();
+    Some((coef, solution[m + 1]))
+}
+
+// local peaks of the error that alternate in sign, trimmed down to r
+fn extremals(grid: &[GridPoint], err: &[f64], r: usize)
+             -> Option<Vec<usize>>
+{
+    let n = err.len();
+    let mut peaks: Vec<usize> = vec![];
+    for i in 0..n {
+        // maxima of positive error, minima of negative error. comparing
+        // magnitudes instead would miss a lone point next to a big swing
+        // of the other sign. band edges only have one neighbor.
+        let sign = if err[i] >= 0.0 { 1.0 } else { -1.0 };
+        let e = sign * err[i];
+        let left = i == 0 || grid[i - 1].band != grid[i].band
+            || e >= sign * err[i - 1];
+        let right = i == n - 1 || grid[i + 1].band != grid[i].band
+            || e >= sign * err[i + 1];
+        if left && right {
+            peaks.push(i);
+        }
+    }
+
+    // of neighbors with the same sign, keep the biggest
+    let mut alt: Vec<usize> = vec![];
+    for i in peaks {
+        if let Some(&last) = alt.last() {
+            if (err[last] >= 0.0) == (err[i] >= 0.0) {
+                if err[i].abs() > err[last].abs() {
+                    *alt.last_mut().unwrap() = i;
+                }
+                continue;
+            }
+        }
+        alt.push(i);
+    }
+
+    // dropping from the ends keeps the alternation
+    while alt.len() > r {
+        if err[alt[0]].abs() < err[alt[alt.len() - 1]].abs() {
+            alt.remove(0);
+        } else {
+            alt.pop();
+        }
+    }
+    if alt.len() < r {
+        None
+    } else {
+        Some(alt)
+    }
+}
+
+impl<A> FilterDesign<A> for Equiripple where A: Convolve<f32> {
+    type Output = A;
+    type Filter = Fir<f32, A>;
+    fn design(self, _rate: f32) -> Self::Filter {
+        Fir::new(self.taps)
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Filter, FilterDesign};
+
+use num::Complex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sideband {
+    Upper,
+    Lower,
+}
+
+#[derive(Clone, Debug)]
+pub struct SsbDesign<F> {
+    sideband: Sideband,
+    bandwidth: f32,
+    bfo: f32,
+    filter: F,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ssb<F> {
+    sideband: Sideband,
+    filter: F,
+
+    // in cycles per sample
+    center: f32,
+    bfo: f32,
+    // from 0 to 1, just so we can keep it bounded
+    ndown: f32,
+    nup: f32,
+}
+
+impl<F> SsbDesign<F> {
+    // filter is applied with the sideband shifted down to be centered on
+    // 0 Hz, so it should be a lowpass with cutoff bandwidth / 2
+    pub fn new(sideband: Sideband, bandwidth: f32, bfo: f32, filter: F)
+               -> Self {
+        SsbDesign {
+            sideband,
+            bandwidth,
+            bfo,
+            filter,
+        }
+    }
+}
+
+impl<F> FilterDesign<Complex<f32>> for SsbDesign<F>
+where
+    F: FilterDesign<Complex<f32>, Output=Complex<f32>>,
+{
+    type Output = f32;
+    type Filter = Ssb<F::Filter>;
+    fn design(self, rate: f32) -> Self::Filter {
+        Ssb {
+            sideband: self.sideband,
+            filter: self.filter.design(rate),
+            center: 0.5 * self.bandwidth / rate,
+            bfo: self.bfo / rate,
+            ndown: 0.0,
+            nup: 0.0,
+        }
+    }
+}
+
+impl<F> Filter<Complex<f32>> for Ssb<F>
+where
+    F: Filter<Complex<f32>, Output=Complex<f32>>,
+{
+    type Output = f32;
+    fn apply(&mut self, value: Complex<f32>) -> Self::Output {
+        use std::f32::consts::PI;
+
+        // lower sideband is just the upper sideband, mirrored
+        let value = match self.sideband {
+            Sideband::Upper => value,
+            Sideband::Lower => value.conj(),
+        };
+
+        let down = Complex::from_polar(&1.0, &(-2.0 * PI * self.ndown));
+        let up = Complex::from_polar(&1.0, &(2.0 * PI * self.nup));
+        self.ndown = (self.ndown + self.center).fract();
+        self.nup = (self.nup + self.center + self.bfo).fract();
+
+        (self.filter.apply(value * down) * up).re
+    }
+}
@@ -0,0 +1,312 @@
+use super::{Filter, FilterDesign};
+use super::{Biquad, BiquadD, Identity, Pll, PllDesign};
+use crate::rds;
+
+use num::Complex;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Deemphasis {
+    None,
+    Us50,
+    Us75,
+}
+
+impl Deemphasis {
+    // time constant, in seconds
+    pub fn time(&self) -> Option<f32> {
+        match self {
+            Deemphasis::None => None,
+            Deemphasis::Us50 => Some(50.0 * 0.001 * 0.001),
+            Deemphasis::Us75 => Some(75.0 * 0.001 * 0.001),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WbfmShared {
+    locked: AtomicBool,
+    // f32 bits
+    blend: AtomicU32,
+    station: Mutex<Option<rds::Station>>,
+}
+
+// a window into a running Wbfm filter, even after it's buried in a Signal
+#[derive(Clone, Debug, Default)]
+pub struct WbfmStatus {
+    shared: Arc<WbfmShared>,
+}
+
+impl WbfmStatus {
+    pub fn locked(&self) -> bool {
+        self.shared.locked.load(Ordering::Relaxed)
+    }
+
+    // 0.0 is mono, 1.0 is full stereo
+    pub fn blend(&self) -> f32 {
+        f32::from_bits(self.shared.blend.load(Ordering::Relaxed))
+    }
+
+    pub fn station(&self) -> Option<rds::Station> {
+        self.shared.station.lock().unwrap().clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WbfmDesign {
+    deemphasis: Deemphasis,
+    stereo: bool,
+    blend: (f32, f32),
+    rds: bool,
+    status: WbfmStatus,
+}
+
+impl Default for WbfmDesign {
+    fn default() -> Self {
+        WbfmDesign {
+            deemphasis: Deemphasis::Us75,
+            stereo: true,
+            blend: (10.0, 30.0),
+            rds: false,
+            status: Default::default(),
+        }
+    }
+}
+
+impl WbfmDesign {
+    // maximum deviation of broadcast FM, in Hz
+    pub const DEVIATION: f32 = 75000.0;
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // turns the raw IQ into the multiplex signal, in Hz of deviation
+    pub fn discriminator() -> PllDesign<BiquadD, Identity, BiquadD> {
+        PllDesign::new(
+            0.0, 0.035,
+            BiquadD::LowPass(80000.0, 0.7),
+            Identity,
+            BiquadD::LowPass(20000.0, 0.7),
+        )
+    }
+
+    pub fn deemphasis(mut self, deemphasis: Deemphasis) -> Self {
+        self.deemphasis = deemphasis;
+        self
+    }
+
+    // false forces mono
+    pub fn stereo(mut self, stereo: bool) -> Self {
+        self.stereo = stereo;
+        self
+    }
+
+    // pilot SNR in dB where we start blending in stereo, and where we
+    // reach full stereo
+    pub fn blend(mut self, mono: f32, stereo: f32) -> Self {
+        self.blend = (mono, stereo);
+        self
+    }
+
+    // decode RDS, and report the station in status()
+    pub fn rds(mut self, rds: bool) -> Self {
+        self.rds = rds;
+        self
+    }
+
+    pub fn status(&self) -> WbfmStatus {
+        self.status.clone()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Audio {
+    notch: Biquad<f32, f32>,
+    lowpass: (Biquad<f32, f32>, Biquad<f32, f32>),
+    deemphasis: Option<Biquad<f32, f32>>,
+}
+
+impl Audio {
+    fn new(rate: f32, deemphasis: Deemphasis) -> Self {
+        let lowpass = |q| BiquadD::LowPass(15000.0, q).design(rate);
+        Audio {
+            notch: BiquadD::Notch(19000.0, 5.0).design(rate),
+            lowpass: (lowpass(0.54), lowpass(1.31)),
+            deemphasis: deemphasis.time()
+                .map(|t| BiquadD::Lr(1.0 / t).design(rate)),
+        }
+    }
+
+    fn apply(&mut self, value: f32) -> f32 {
+        let value = self.notch.apply(value);
+        let value = self.lowpass.0.apply(value);
+        let value = self.lowpass.1.apply(value);
+        if let Some(ref mut d) = self.deemphasis {
+            d.apply(value)
+        } else {
+            value
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Wbfm {
+    pilot: Pll<Biquad<f32, Complex<f32>>, Biquad<f32, f32>, Biquad<f32, f32>>,
+    pilotband: (Biquad<f32, Complex<f32>>, Biquad<f32, Complex<f32>>),
+    pilotlevel: Biquad<f32, Complex<f32>>,
+    pilotnoise: Biquad<f32, f32>,
+
+    mono: Audio,
+    diff: Audio,
+
+    stereo: bool,
+    blend: (f32, f32),
+    blendvalue: f32,
+
+    rds: Option<(rds::Rds, rds::Station)>,
+    status: WbfmStatus,
+}
+
+impl FilterDesign<f32> for WbfmDesign {
+    type Output = (f32, f32);
+    type Filter = Wbfm;
+    fn design(self, rate: f32) -> Self::Filter {
+        Wbfm {
+            pilot: PllDesign::new(
+                19000.0, 0.0002,
+                BiquadD::LowPass(200.0, 0.7),
+                BiquadD::LowPass(20.0, 0.7),
+                BiquadD::LowPass(20.0, 0.7),
+            ).design(rate),
+            pilotband: (BiquadD::LowPass(2000.0, 0.54).design(rate),
+                        BiquadD::LowPass(2000.0, 1.31).design(rate)),
+            pilotlevel: BiquadD::LowPass(20.0, 0.7).design(rate),
+            pilotnoise: BiquadD::LowPass(20.0, 0.7).design(rate),
+
+            mono: Audio::new(rate, self.deemphasis),
+            diff: Audio::new(rate, self.deemphasis),
+
+            stereo: self.stereo,
+            blend: self.blend,
+            blendvalue: 0.0,
+
+            rds: if self.rds {
+                Some((rds::RdsDesign.design(rate), rds::Station::new()))
+            } else {
+                None
+            },
+            status: self.status,
+        }
+    }
+}
+
+impl Wbfm {
+    pub fn locked(&self) -> bool {
+        self.status.locked()
+    }
+
+    pub fn blend(&self) -> f32 {
+        self.blendvalue
+    }
+}
+
+impl Filter<f32> for Wbfm {
+    type Output = (f32, f32);
+    fn apply(&mut self, value: f32) -> Self::Output {
+        if let Some((ref mut decoder, ref mut station)) = self.rds {
+            if let Some(group) = decoder.apply(value) {
+                if station.update(&group) {
+                    let mut shared = self.status.shared.station.lock()
+                        .unwrap();
+                    *shared = Some(station.clone());
+                }
+            }
+        }
+
+        // the pll's estimate for this sample, before it sees it
+        let pilot = self.pilot.value;
+        let locked = self.pilot.apply(Complex::new(value, 0.0)).is_some();
+
+        // pilot quality is how much the pilot stands out from its
+        // neighborhood, which is empty in a clean signal
+        let c = self.pilotband.0.apply(value * pilot.conj());
+        let c = self.pilotband.1.apply(c);
+        let level = self.pilotlevel.apply(c);
+        let noise = self.pilotnoise.apply((c - level).norm_sqr());
+        let snr = 10.0 * (level.norm_sqr() / noise).log10();
+        self.blendvalue = if self.stereo && locked {
+            let (mono, stereo) = self.blend;
+            ((snr - mono) / (stereo - mono)).max(0.0).min(1.0)
+        } else {
+            0.0
+        };
+        self.status.shared.locked.store(locked, Ordering::Relaxed);
+        self.status.shared.blend.store(self.blendvalue.to_bits(),
+                                       Ordering::Relaxed);
+
+        // the stereo subcarrier is phase locked with zero crossings that
+        // line up with the pilot, which is 90 degrees off of pilot^2
+        let diff = if locked {
+            2.0 * (value * pilot.powi(2).conj()).im
+        } else {
+            0.0
+        };
+
+        let mono = self.mono.apply(value);
+        let diff = self.diff.apply(diff) * self.blendvalue;
+        (0.5 * (mono + diff), 0.5 * (mono - diff))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    const RATE: f32 = 144000.0;
+
+    // a clean multiplex with a 1kHz tone on the left only, and the pilot
+    fn left_only(i: usize) -> f32 {
+        let t = i as f32 / RATE;
+        let pilot = 2.0 * PI * 19000.0 * (t % 1.0);
+        let l = 0.4 * (2.0 * PI * 1000.0 * t).sin();
+        0.45 * l + 0.45 * l * (2.0 * pilot).sin() + 0.09 * pilot.sin()
+    }
+
+    // power on each side over the second second, after the pilot locks
+    fn decode(design: WbfmDesign) -> (f32, f32) {
+        let mut wbfm = design.design(RATE);
+        let (mut left, mut right) = (0.0, 0.0);
+        for i in 0..2 * RATE as usize {
+            let (l, r) = wbfm.apply(left_only(i));
+            if i >= RATE as usize {
+                left += l * l;
+                right += r * r;
+            }
+        }
+        (left, right)
+    }
+
+    #[test]
+    fn stereo_separates() {
+        let design = WbfmDesign::new();
+        let status = design.status();
+        let (left, right) = decode(design);
+        assert!(status.locked());
+        assert!(status.blend() > 0.9, "blend {}", status.blend());
+        assert!(left > 100.0 * right, "left {} right {}", left, right);
+    }
+
+    #[test]
+    fn mono_is_mono() {
+        let design = WbfmDesign::new().stereo(false);
+        let status = design.status();
+        let (left, right) = decode(design);
+        assert!(status.locked());
+        assert_eq!(status.blend(), 0.0);
+        assert!(left > 0.0);
+        assert_eq!(left, right);
+    }
+}
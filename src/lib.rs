@@ -11,6 +11,10 @@ pub mod resample;
 
 pub mod rtltcp;
 
+pub mod rds;
+
+pub mod sigmf;
+
 pub mod plot;
 
 pub mod fft;
@@ -14,6 +14,22 @@ fn main() -> std::io::Result<()> {
              .value_name("ADDRESS")
              .default_value("localhost:1234")
              .takes_value(true))
+        .arg(clap::Arg::with_name("gain")
+             .short("g")
+             .long("gain")
+             .value_name("DB")
+             .help("Tuner gain, rounded to the nearest step. Auto if missing.")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("ppm")
+             .short("p")
+             .long("ppm")
+             .value_name("PPM")
+             .help("Frequency correction, in parts per million.")
+             .takes_value(true)
+             .default_value("0"))
+        .arg(clap::Arg::with_name("bias-tee")
+             .long("bias-tee")
+             .help("Power an active antenna through the bias tee."))
         .arg(clap::Arg::with_name("output")
              .short("o")
              .long("output")
@@ -27,59 +43,230 @@ fn main() -> std::io::Result<()> {
              .help("How long to record, if recording.")
              .takes_value(true)
              .default_value("10"))
+        .arg(clap::Arg::with_name("rds")
+             .short("r")
+             .long("rds")
+             .value_name("FILE")
+             .help("Log decoded RDS station info to a file.")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("mono")
+             .short("m")
+             .long("mono")
+             .help("Force mono output."))
+        .arg(clap::Arg::with_name("deemphasis")
+             .long("deemphasis")
+             .value_name("MICROSECONDS")
+             .help("De-emphasis time constant.")
+             .possible_values(&["50", "75"])
+             .takes_value(true)
+             .default_value("75"))
+        .arg(clap::Arg::with_name("nbfm")
+             .long("nbfm")
+             .value_name("CHANNEL")
+             .help("Listen to narrowband FM instead of broadcast FM.")
+             .possible_values(&["narrow", "wide"])
+             .takes_value(true))
+        .arg(clap::Arg::with_name("ctcss")
+             .long("ctcss")
+             .value_name("HZ")
+             .help("Only play NBFM audio with this CTCSS tone.")
+             .conflicts_with("dcs")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("dcs")
+             .long("dcs")
+             .value_name("CODE")
+             .help("Only play NBFM audio with this DCS code, like D023N.")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("squelch")
+             .short("s")
+             .long("squelch")
+             .value_name("DB")
+             .help("Silence NBFM while the channel SNR is below this.")
+             .requires("nbfm")
+             .takes_value(true))
+        .arg(clap::Arg::with_name("record")
+             .long("record")
+             .value_name("FILE")
+             .help("Also record raw IQ to a .cu8, .cs8, .cs16 or .cf32 file.")
+             .takes_value(true))
         .get_matches();
 
     let rate = 1800000;
     use clap::value_t_or_exit;
     let rtl = rtltcp::RtlTcp::new()
-        .address(matches.value_of("address").unwrap())
-        .rate(rate)
-        .gain(None)
+        .address(matches.value_of("address").unwrap())?
+        .rate(rate)?
+        .gain(if matches.is_present("gain") {
+            Some(value_t_or_exit!(matches, "gain", f32))
+        } else {
+            None
+        })?
+        .ppm(value_t_or_exit!(matches, "ppm", i32))
+        .bias_tee(matches.is_present("bias-tee"))
         .rtlagc(true)
-        .frequency((value_t_or_exit!(matches, "FREQ", f32) * 1000000.0) as u32);
+        .frequency((value_t_or_exit!(matches, "FREQ", f32) * 1000000.0) as u32)?;
+
+    let iq = rtl.listen()?;
+    match matches.value_of("record") {
+        Some(path) => {
+            let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+            let format = signal::IqFormat::from_extension(path)
+                .unwrap_or(signal::IqFormat::Cu8);
+            demodulate(iq.record(writer, format).block(0.1), &matches)
+        },
+        None => demodulate(iq.block(0.1), &matches),
+    }
+}
+
+// NBFM or broadcast FM audio from the raw IQ
+fn demodulate<S>(iq: S, matches: &clap::ArgMatches) -> std::io::Result<()>
+where
+    S: Signal<Sample=num::Complex<f32>> + Send + 'static,
+{
+    use clap::value_t_or_exit;
+    if let Some(channel) = matches.value_of("nbfm") {
+        let channel = match channel {
+            "wide" => filter::NbfmChannel::Wide,
+            _ => filter::NbfmChannel::Narrow,
+        };
+        // just the channel, by carson's rule, so the squelch only hears
+        // this station. long enough that the fft is worth it
+        let iq = iq
+            .resample_with(resample::ConverterType::SincFastest, 48000.0)
+            .block(0.1)
+            .filter(filter::AutoFirD(filter::FirD::LowPass(
+                channel.deviation() + 3000.0,
+                filter::Window::Kaiser(2000.0, 60.0))));
+
+        if matches.is_present("squelch") {
+            let open = value_t_or_exit!(matches, "squelch", f32);
+            let squelch = signal::SquelchDesign::snr(open, open - 3.0)
+                .tail(0.5);
+            let iq = iq.squelch(squelch, |event| match event {
+                signal::SquelchEvent::Open(_) => println!("squelch open"),
+                signal::SquelchEvent::Close(_) => println!("squelch closed"),
+            });
+            return nbfm(iq, channel, matches);
+        }
+        return nbfm(iq, channel, matches);
+    }
 
-    let pllf = filter::PllDesign::new(
-        0.0, 0.035,
-        filter::BiquadD::LowPass(80000.0, 0.7),
-        filter::Identity,
-        filter::BiquadD::LowPass(20000.0, 0.7),
-    );
+    let deemphasis = match value_t_or_exit!(matches, "deemphasis", u32) {
+        50 => filter::Deemphasis::Us50,
+        _ => filter::Deemphasis::Us75,
+    };
+    let design = filter::WbfmDesign::new()
+        .deemphasis(deemphasis)
+        .stereo(!matches.is_present("mono"))
+        .rds(true);
+    let status = design.status();
 
-    let fm = rtl.listen()?.block(0.1);
-    let fm = fm.filter(pllf).map(|f| f.unwrap_or(0.0) / 75000.0).block(0.1);
-    let fm = fm.resample_with(resample::ConverterType::SincFastest, 48000.0 * 3.0).block(0.1);
+    let fm = iq.wbfm_stereo(design, 48000.0).block(0.1);
 
-    let deemph = filter::BiquadD::Lr(1.0 / (75.0 * 0.001 * 0.001));
+    let mut rdslog = match matches.value_of("rds") {
+        Some(path) => Some(std::fs::File::create(path)?),
+        None => None,
+    };
+    std::thread::spawn(move || {
+        let mut last = String::new();
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(1));
+            let station = match status.station() {
+                Some(station) => station.to_string(),
+                None => continue,
+            };
+            if station == last {
+                continue;
+            }
+            println!("rds {}", station);
+            if let Some(ref mut log) = rdslog {
+                use std::io::Write;
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                writeln!(log, "{} {}", now, station).ok();
+            }
+            last = station;
+        }
+    });
 
-    let pilotf = 19000.0;
-    let mut pllpilot = filter::PllDesign::new(
-        pilotf, 0.0002,
-        filter::BiquadD::LowPass(200.0, 0.7),
-        filter::BiquadD::LowPass(20.0, 0.7),
-        filter::BiquadD::LowPass(20.0, 0.7),
-    ).design(fm.rate());
+    play(fm, matches)
+}
 
-    let fm = fm.map(move |v| {
-        let mono = v * 0.5;
-        let diff = if let Some(_) = pllpilot.apply(num::Complex::new(v, 0.0)) {
-            let diffc = v / pllpilot.value.powi(2);
-            diffc.re * 0.5
-        } else {
-            0.0
+// NBFM audio from channel filtered IQ, with an optional tone squelch
+fn nbfm<S>(iq: S, channel: filter::NbfmChannel, matches: &clap::ArgMatches)
+           -> std::io::Result<()>
+where
+    S: Signal<Sample=num::Complex<f32>> + Send + 'static,
+{
+    use clap::value_t_or_exit;
+    let nbfm = iq
+        .filter(filter::NbfmDesign::new(channel))
+        // talkers vary a lot more than broadcast stations
+        .filter(filter::AgcDesign::new().hang(0.2));
+
+    if let Some(code) = matches.value_of("dcs") {
+        let code = match parse_dcs(code) {
+            Some(code) => code,
+            None => clap::Error::value_validation_auto(
+                format!("bad DCS code: {}", code)).exit(),
         };
-        (mono, diff)
-    }).block(0.1).monitor(1.0, |v| println!("monitor {:?}", v));
+        let mut last = None;
+        let fm = nbfm.filter(filter::DcsDesign::new(Some(code)))
+            .map(move |(x, code)| {
+                if code != last {
+                    match code {
+                        Some(code) => println!("dcs {}", code),
+                        None => println!("dcs none"),
+                    }
+                    last = code;
+                }
+                (x, x)
+            });
+        return play(fm.block(0.1), matches);
+    }
 
-    let fm = fm.resample(48000.0).block(0.1);
+    let tone = if matches.is_present("ctcss") {
+        Some(value_t_or_exit!(matches, "ctcss", f32))
+    } else {
+        None
+    };
+    let mut last = None;
+    let fm = nbfm.filter(filter::CtcssDesign::new(tone))
+        .map(move |(x, tone)| {
+            if tone != last {
+                match tone {
+                    Some(tone) => println!("ctcss {:.1}", tone),
+                    None => println!("ctcss none"),
+                }
+                last = tone;
+            }
+            (x, x)
+        });
+    play(fm.block(0.1), matches)
+}
 
-    let mut monod = deemph.clone().design(fm.rate());
-    let mut diffd = deemph.clone().design(fm.rate());
-    let fm = fm.map(move |(monov, diffv)| {
-        let mono = monod.apply(monov);
-        let diff = diffd.apply(diffv);
-        (mono + diff, mono - diff)
-    }).block(0.1);
+// D023N, 023N, or 023
+fn parse_dcs(code: &str) -> Option<filter::DcsCode> {
+    let code = code.trim_start_matches(|c| c == 'D' || c == 'd');
+    let (code, inverted) = match code.chars().last()? {
+        'N' | 'n' => (&code[..code.len() - 1], false),
+        'I' | 'i' => (&code[..code.len() - 1], true),
+        _ => (code, false),
+    };
+    let code = u16::from_str_radix(code, 8).ok()?;
+    if code > 0o777 {
+        return None;
+    }
+    Some(filter::DcsCode::new(code, inverted))
+}
 
+fn play<S>(fm: S, matches: &clap::ArgMatches) -> std::io::Result<()>
+where
+    S: Signal<Sample=(f32, f32)> + Send + 'static,
+{
+    use clap::value_t_or_exit;
     if let Some(outfile) = matches.value_of("output") {
         let spec = hound::WavSpec {
             channels: 2,
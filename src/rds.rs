@@ -0,0 +1,547 @@
+use crate::filter::{self, Filter, FilterDesign};
+
+use num::Complex;
+use std::collections::{HashMap, VecDeque};
+
+// RDS is BPSK on a 57kHz subcarrier, locked to the third harmonic of the
+// 19kHz stereo pilot. each bit is biphase coded, so there are two chips
+// per bit.
+const BIT_RATE: f32 = 1187.5;
+const CHIP_RATE: f32 = 2.0 * BIT_RATE;
+
+// offset words for blocks A, B, C, C', D
+const OFFSET_A: u16 = 0x0fc;
+const OFFSET_B: u16 = 0x198;
+const OFFSET_C: u16 = 0x168;
+const OFFSET_CP: u16 = 0x350;
+const OFFSET_D: u16 = 0x1b4;
+const OFFSETS: [u16; 4] = [OFFSET_A, OFFSET_B, OFFSET_C, OFFSET_D];
+
+// x^10 + x^8 + x^7 + x^5 + x^4 + x^3 + 1
+const POLY: u32 = 0x5b9;
+
+// longest burst error we will try to correct
+const MAX_BURST: u32 = 2;
+
+// lose sync if this many of the last SYNC_WINDOW blocks were bad
+const SYNC_WINDOW: usize = 12;
+const SYNC_BAD: usize = 6;
+
+fn checkword(data: u16) -> u16 {
+    let mut reg = (data as u32) << 10;
+    for i in (10..26).rev() {
+        if reg & (1 << i) != 0 {
+            reg ^= POLY << (i - 10);
+        }
+    }
+    (reg & 0x3ff) as u16
+}
+
+// for a valid block, this is the offset word
+fn syndrome(block: u32) -> u16 {
+    let data = (block >> 10) as u16;
+    let check = (block & 0x3ff) as u16;
+    check ^ checkword(data)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Version {
+    A,
+    B,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Group {
+    // None if the block was missing or uncorrectable
+    pub blocks: [Option<u16>; 4],
+}
+
+impl Group {
+    pub fn pi(&self) -> Option<u16> {
+        // version B groups repeat PI in block C'
+        self.blocks[0].or_else(|| match self.version() {
+            Some(Version::B) => self.blocks[2],
+            _ => None,
+        })
+    }
+
+    pub fn group_type(&self) -> Option<u8> {
+        self.blocks[1].map(|b| (b >> 12) as u8)
+    }
+
+    pub fn version(&self) -> Option<Version> {
+        self.blocks[1].map(|b| {
+            if b & 0x0800 == 0 { Version::A } else { Version::B }
+        })
+    }
+
+    pub fn tp(&self) -> Option<bool> {
+        self.blocks[1].map(|b| b & 0x0400 != 0)
+    }
+
+    pub fn pty(&self) -> Option<u8> {
+        self.blocks[1].map(|b| ((b >> 5) & 0x1f) as u8)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BlockSync {
+    register: u32,
+    bits: usize,
+
+    // corrections, indexed by syndrome
+    bursts: HashMap<u16, u32>,
+
+    // when unsynced, recent (bit position, block index) matches
+    candidates: VecDeque<(usize, usize)>,
+    synced: bool,
+    expected: usize,
+    position: usize,
+    history: VecDeque<bool>,
+
+    group: [Option<u16>; 4],
+}
+
+impl BlockSync {
+    fn new() -> Self {
+        let mut bursts = HashMap::new();
+        for length in 1..=MAX_BURST {
+            // bursts must start and end with an error
+            let middles = if length > 2 { 1 << (length - 2) } else { 1 };
+            for middle in 0..middles {
+                let pattern = (1 << (length - 1)) | (middle << 1) | 1;
+                for shift in 0..(26 - length + 1) {
+                    let error = pattern << shift;
+                    bursts.entry(syndrome(error)).or_insert(error);
+                }
+            }
+        }
+
+        BlockSync {
+            register: 0,
+            bits: 0,
+            bursts,
+            candidates: VecDeque::new(),
+            synced: false,
+            expected: 0,
+            position: 0,
+            history: VecDeque::with_capacity(SYNC_WINDOW),
+            group: [None; 4],
+        }
+    }
+
+    fn correct(&self, block: u32, index: usize) -> Option<u16> {
+        let offsets: &[u16] = if index == 2 {
+            &[OFFSET_C, OFFSET_CP]
+        } else {
+            &OFFSETS[index..index + 1]
+        };
+        let s = syndrome(block);
+        for offset in offsets {
+            if s == *offset {
+                return Some((block >> 10) as u16);
+            }
+            if let Some(error) = self.bursts.get(&(s ^ offset)) {
+                return Some(((block ^ error) >> 10) as u16);
+            }
+        }
+        None
+    }
+
+    fn acquire(&mut self) {
+        let s = syndrome(self.register);
+        let found = match s {
+            OFFSET_A => Some(0),
+            OFFSET_B => Some(1),
+            OFFSET_C | OFFSET_CP => Some(2),
+            OFFSET_D => Some(3),
+            _ => None,
+        };
+
+        if let Some(index) = found {
+            // does this agree with an earlier match?
+            for (pos, previndex) in self.candidates.iter() {
+                let distance = self.bits - pos;
+                let blocks = (index + 4 - previndex) % 4;
+                if distance % 26 == 0 && (distance / 26) % 4 == blocks {
+                    self.synced = true;
+                    self.history.clear();
+                    self.group = [None; 4];
+                    self.group[index] = Some((self.register >> 10) as u16);
+                    self.expected = (index + 1) % 4;
+                    self.position = 0;
+                    self.candidates.clear();
+                    return;
+                }
+            }
+            self.candidates.push_back((self.bits, index));
+        }
+
+        while let Some((pos, _)) = self.candidates.front() {
+            if self.bits - pos > 26 * 4 {
+                self.candidates.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn push(&mut self, bit: bool) -> Option<Group> {
+        self.register = ((self.register << 1) | bit as u32) & 0x3ff_ffff;
+        self.bits += 1;
+
+        if !self.synced {
+            self.acquire();
+            // if we synced on block D, we are at the end of a group
+            if self.synced && self.expected == 0 {
+                return Some(self.take_group());
+            }
+            return None;
+        }
+
+        self.position += 1;
+        if self.position < 26 {
+            return None;
+        }
+        self.position = 0;
+
+        let index = self.expected;
+        let block = self.correct(self.register, index);
+        self.group[index] = block;
+        self.expected = (index + 1) % 4;
+
+        if self.history.len() >= SYNC_WINDOW {
+            self.history.pop_front();
+        }
+        self.history.push_back(block.is_none());
+        if self.history.iter().filter(|bad| **bad).count() >= SYNC_BAD {
+            self.synced = false;
+            self.candidates.clear();
+        }
+
+        if index == 3 {
+            Some(self.take_group())
+        } else {
+            None
+        }
+    }
+
+    fn take_group(&mut self) -> Group {
+        let group = Group { blocks: self.group };
+        self.group = [None; 4];
+        group
+    }
+}
+
+type Loop = filter::Biquad<f32, Complex<f32>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RdsDesign;
+
+#[derive(Clone, Debug)]
+pub struct Rds {
+    pilot: filter::Pll<Loop, filter::Biquad<f32, f32>, filter::Biquad<f32, f32>>,
+    lowpass: (Loop, Loop),
+    carrier: Loop,
+
+    // chip clock, in chips
+    chipstep: f32,
+    chipphase: f32,
+    chipsum: f32,
+    last: f32,
+
+    // which chip boundaries fall in the middle of a bit
+    lastchip: f32,
+    parity: usize,
+    transitions: [f32; 2],
+
+    lastbit: bool,
+    sync: BlockSync,
+}
+
+impl FilterDesign<f32> for RdsDesign {
+    type Output = Option<Group>;
+    type Filter = Rds;
+    fn design(self, rate: f32) -> Self::Filter {
+        let lowpass = |q| filter::BiquadD::LowPass(2400.0, q).design(rate);
+        Rds {
+            pilot: filter::PllDesign::new(
+                19000.0, 0.0002,
+                filter::BiquadD::LowPass(200.0, 0.7),
+                filter::BiquadD::LowPass(20.0, 0.7),
+                filter::BiquadD::LowPass(20.0, 0.7),
+            ).design(rate),
+            lowpass: (lowpass(0.54), lowpass(1.31)),
+            carrier: filter::BiquadD::LowPass(10.0, 0.7).design(rate),
+
+            chipstep: CHIP_RATE / rate,
+            chipphase: 0.0,
+            chipsum: 0.0,
+            last: 0.0,
+
+            lastchip: 0.0,
+            parity: 0,
+            transitions: [0.0; 2],
+
+            lastbit: false,
+            sync: BlockSync::new(),
+        }
+    }
+}
+
+impl Rds {
+    fn chip(&mut self, chip: f32) -> Option<Group> {
+        // every bit has a transition in the middle, so those chip
+        // boundaries see more change on average
+        self.parity ^= 1;
+        let change = (self.lastchip - chip).abs();
+        let t = &mut self.transitions[self.parity];
+        *t = 0.99 * *t + 0.01 * change;
+        let middle = self.transitions[self.parity]
+            > self.transitions[self.parity ^ 1];
+
+        let first = self.lastchip;
+        self.lastchip = chip;
+        if !middle {
+            return None;
+        }
+
+        // differential decoding also takes care of our phase ambiguity
+        let bit = first - chip > 0.0;
+        let data = bit != self.lastbit;
+        self.lastbit = bit;
+        self.sync.push(data)
+    }
+}
+
+impl Filter<f32> for Rds {
+    type Output = Option<Group>;
+    fn apply(&mut self, value: f32) -> Self::Output {
+        self.pilot.apply(Complex::new(value, 0.0))?;
+
+        // bring the 57kHz subcarrier down to baseband
+        let baseband = value * self.pilot.value.powi(3).conj();
+        let baseband = self.lowpass.0.apply(baseband);
+        let baseband = self.lowpass.1.apply(baseband);
+
+        // BPSK, so squaring removes the modulation and leaves the phase
+        let carrier = self.carrier.apply(baseband.powi(2));
+        let rotate = Complex::from_polar(&1.0, &(-carrier.arg() / 2.0));
+        let sample = (baseband * rotate).re;
+
+        // chip boundaries are where the signal crosses zero
+        let start = self.chipphase;
+        self.chipphase += self.chipstep;
+        if (sample > 0.0) != (self.last > 0.0) {
+            let frac = self.last / (self.last - sample);
+            let mut error = (start + frac * self.chipstep).fract();
+            if error > 0.5 {
+                error -= 1.0;
+            }
+            self.chipphase -= 0.05 * error;
+        }
+        self.last = sample;
+
+        self.chipsum += sample;
+        if self.chipphase >= 1.0 {
+            self.chipphase -= 1.0;
+            let chip = self.chipsum;
+            self.chipsum = 0.0;
+            self.chip(chip)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ClockTime {
+    // modified julian day
+    pub mjd: u32,
+    // UTC
+    pub hour: u8,
+    pub minute: u8,
+    // local offset, in half hours
+    pub offset: i8,
+}
+
+impl ClockTime {
+    // (year, month, day), UTC
+    pub fn date(&self) -> (i32, u8, u8) {
+        // straight from the RDS standard, annex G
+        let mjd = self.mjd as f64;
+        let yp = ((mjd - 15078.2) / 365.25).floor();
+        let mp = ((mjd - 14956.1 - (yp * 365.25).floor()) / 30.6001).floor();
+        let day = mjd - 14956.0 - (yp * 365.25).floor()
+            - (mp * 30.6001).floor();
+        let k = if mp == 14.0 || mp == 15.0 { 1.0 } else { 0.0 };
+        let year = yp + k + 1900.0;
+        let month = mp - 1.0 - k * 12.0;
+        (year as i32, month as u8, day as u8)
+    }
+}
+
+impl std::fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (year, month, day) = self.date();
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.abs() as u32 * 30;
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02} UTC{}{:02}:{:02}",
+               year, month, day, self.hour, self.minute,
+               sign, offset / 60, offset % 60)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Station {
+    pub pi: Option<u16>,
+    pub pty: Option<u8>,
+    pub tp: Option<bool>,
+    pub time: Option<ClockTime>,
+    ps: [u8; 8],
+    radiotext: [u8; 64],
+    radiotext_ab: Option<bool>,
+}
+
+impl Default for Station {
+    fn default() -> Self {
+        Station {
+            pi: None,
+            pty: None,
+            tp: None,
+            time: None,
+            ps: [b' '; 8],
+            radiotext: [b' '; 64],
+            radiotext_ab: None,
+        }
+    }
+}
+
+fn rds_char(c: u8) -> char {
+    // the RDS character set agrees with ASCII on the printable range
+    if (0x20..0x7f).contains(&c) {
+        c as char
+    } else {
+        '?'
+    }
+}
+
+impl Station {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // returns true if anything we know changed
+    pub fn update(&mut self, group: &Group) -> bool {
+        let old = (self.pi, self.pty, self.tp, self.time,
+                   self.ps, self.radiotext);
+
+        if let Some(pi) = group.pi() {
+            if self.pi != Some(pi) {
+                // new station, forget everything
+                *self = Station::new();
+                self.pi = Some(pi);
+            }
+        }
+        if group.pty().is_some() {
+            self.pty = group.pty();
+            self.tp = group.tp();
+        }
+
+        let b = group.blocks[1];
+        let c = group.blocks[2];
+        let d = group.blocks[3];
+        match (group.group_type(), group.version(), b) {
+            (Some(0), _, Some(b)) => {
+                if let Some(d) = d {
+                    let addr = (b & 0x3) as usize * 2;
+                    self.ps[addr] = (d >> 8) as u8;
+                    self.ps[addr + 1] = d as u8;
+                }
+            },
+            (Some(2), Some(version), Some(b)) => {
+                let ab = b & 0x10 != 0;
+                if self.radiotext_ab.map(|old| old != ab).unwrap_or(false) {
+                    self.radiotext = [b' '; 64];
+                }
+                self.radiotext_ab = Some(ab);
+
+                let addr = (b & 0xf) as usize;
+                match (version, c, d) {
+                    (Version::A, Some(c), Some(d)) => {
+                        let chars = [(c >> 8) as u8, c as u8,
+                                     (d >> 8) as u8, d as u8];
+                        self.radiotext[addr * 4..addr * 4 + 4]
+                            .copy_from_slice(&chars);
+                    },
+                    (Version::B, _, Some(d)) => {
+                        let chars = [(d >> 8) as u8, d as u8];
+                        self.radiotext[addr * 2..addr * 2 + 2]
+                            .copy_from_slice(&chars);
+                    },
+                    _ => {},
+                }
+            },
+            (Some(4), Some(Version::A), Some(b)) => {
+                if let (Some(c), Some(d)) = (c, d) {
+                    let offset = (d & 0x1f) as i8;
+                    self.time = Some(ClockTime {
+                        mjd: ((b as u32 & 0x3) << 15) | (c as u32 >> 1),
+                        hour: (((c & 0x1) << 4) | (d >> 12)) as u8,
+                        minute: ((d >> 6) & 0x3f) as u8,
+                        offset: if d & 0x20 != 0 { -offset } else { offset },
+                    });
+                }
+            },
+            _ => {},
+        }
+
+        old != (self.pi, self.pty, self.tp, self.time,
+                self.ps, self.radiotext)
+    }
+
+    pub fn ps(&self) -> String {
+        self.ps.iter().cloned().map(rds_char).collect()
+    }
+
+    pub fn radiotext(&self) -> String {
+        // carriage return marks the end of shorter messages
+        let end = self.radiotext.iter().position(|c| *c == 0x0d)
+            .unwrap_or(self.radiotext.len());
+        let text: String = self.radiotext[..end].iter().cloned()
+            .map(rds_char).collect();
+        text.trim_end().to_owned()
+    }
+
+    // North American stations encode their call sign in PI
+    pub fn callsign(&self) -> Option<String> {
+        let pi = self.pi? as u32;
+        let (first, n) = if (21672..=39247).contains(&pi) {
+            ('W', pi - 21672)
+        } else if (4096..21672).contains(&pi) {
+            ('K', pi - 4096)
+        } else {
+            return None;
+        };
+        let letter = |i| (b'A' + i as u8) as char;
+        Some([first, letter(n / 676), letter((n / 26) % 26), letter(n % 26)]
+             .iter().collect())
+    }
+}
+
+impl std::fmt::Display for Station {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.pi {
+            Some(pi) => write!(f, "PI {:04X}", pi)?,
+            None => write!(f, "PI ????")?,
+        }
+        if let Some(pty) = self.pty {
+            write!(f, " PTY {:2}", pty)?;
+        }
+        write!(f, " PS \"{}\"", self.ps())?;
+        if let Some(ref time) = self.time {
+            write!(f, " CT {}", time)?;
+        }
+        write!(f, " RT \"{}\"", self.radiotext())
+    }
+}
@@ -280,3 +280,62 @@ unsafe impl<F> Resample for num::Complex<F> where F: Resample {
 unsafe impl<A, B> Resample for (A, B) where A: Resample, B: Resample {
     fn channels() -> usize { A::channels() + B::channels() }
 }
+
+// a pure-rust polyphase L/M resampler, for resample_with. the passband
+// runs up to bandwidth times the lower nyquist rate, and everything past
+// the lower nyquist rate is attenuated by attenuation dB
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Polyphase {
+    pub bandwidth: f32,
+    pub attenuation: f32,
+}
+
+impl Default for Polyphase {
+    fn default() -> Self {
+        Polyphase::new()
+    }
+}
+
+impl Polyphase {
+    pub fn new() -> Self {
+        Polyphase {
+            bandwidth: 0.9,
+            attenuation: 80.0,
+        }
+    }
+
+    // at most 0.99, anything closer to nyquist leaves no room to roll off
+    pub fn bandwidth(mut self, bandwidth: f32) -> Self {
+        self.bandwidth = bandwidth.min(0.99);
+        self
+    }
+
+    pub fn attenuation(mut self, attenuation: f32) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    // closest (up, down) with both at most max, by continued fractions
+    pub fn ratio(from: f32, to: f32, max: u64) -> (u64, u64) {
+        let target = to as f64 / from as f64;
+        let (mut h0, mut h1) = (0u64, 1u64);
+        let (mut k0, mut k1) = (1u64, 0u64);
+        let mut x = target;
+        loop {
+            let a = x.floor();
+            let h2 = a as u64 * h1 + h0;
+            let k2 = a as u64 * k1 + k0;
+            if h2 > max || k2 > max {
+                break;
+            }
+            h0 = h1; h1 = h2;
+            k0 = k1; k1 = k2;
+            let err = (h1 as f64 / k1 as f64 - target).abs();
+            if err <= 1e-9 * target || x == a {
+                break;
+            }
+            x = 1.0 / (x - a);
+        }
+        (h1.max(1), k1.max(1))
+    }
+}
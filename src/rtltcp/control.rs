@@ -0,0 +1,79 @@
+use super::{DongleInfo, Error, Result, RtlTcpCommand};
+use super::error::{check_frequency, check_gain};
+
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use byteorder::{BigEndian, WriteBytesExt};
+
+// retunes a running RtlTcpSignal from anywhere, over the same socket
+#[derive(Debug, Clone)]
+pub struct RtlTcpControl {
+    writer: Arc<Mutex<TcpStream>>,
+    // samples handed out by the RtlTcpSignal so far
+    count: Arc<AtomicU64>,
+    info: DongleInfo,
+}
+
+impl RtlTcpControl {
+    pub(super) fn new(writer: Arc<Mutex<TcpStream>>, count: Arc<AtomicU64>,
+                      info: DongleInfo) -> Self
+    {
+        RtlTcpControl {
+            writer,
+            count,
+            info,
+        }
+    }
+
+    pub fn info(&self) -> DongleInfo {
+        self.info
+    }
+
+    // index of the next sample the signal will hand out. anything before
+    // this was certainly captured with the old settings.
+    pub fn sample_index(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    // returns the index of the next sample the signal hands out, as the
+    // command goes out. samples already buffered in the dongle, the
+    // network and the reader still have the old settings, so treat it as
+    // a lower bound.
+    pub fn command(&self, cmd: RtlTcpCommand) -> Result<u64> {
+        if let RtlTcpCommand::SetSampleRate(_) = cmd {
+            // the signal's rate is already baked into everything downstream
+            return Err(Error::LiveSampleRate);
+        }
+        send(&self.writer, cmd)?;
+        Ok(self.sample_index())
+    }
+
+    pub fn set_frequency(&self, frequency: u32) -> Result<u64> {
+        self.command(RtlTcpCommand::SetFrequency(check_frequency(frequency)?))
+    }
+
+    // in dB, rounded to the nearest step the tuner has. None means Auto
+    pub fn set_gain(&self, gain: Option<f32>) -> Result<u64> {
+        if let Some(gain) = gain {
+            let gain = self.info.nearest_gain(check_gain(gain)?);
+            self.command(RtlTcpCommand::SetTunerGainMode(1))?;
+            self.command(RtlTcpCommand::SetTunerGain(gain))
+        } else {
+            self.command(RtlTcpCommand::SetTunerGainMode(0))
+        }
+    }
+}
+
+// one lock per command, so commands from different handles never mix
+pub(super) fn send(writer: &Mutex<TcpStream>, cmd: RtlTcpCommand)
+                   -> Result<()>
+{
+    let (cmdi, arg) = cmd.encode();
+    let mut buf = Vec::with_capacity(5);
+    buf.write_u8(cmdi)?;
+    buf.write_u32::<BigEndian>(arg)?;
+    writer.lock().unwrap().write_all(&buf)?;
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use super::{Error, Result};
+use byteorder::{BigEndian, ByteOrder};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TunerType {
+    Unknown,
+    E4000,
+    Fc0012,
+    Fc0013,
+    Fc2580,
+    R820T,
+    R828D,
+}
+
+// gain steps, in 10ths of dB, straight from librtlsdr
+const E4000_GAINS: &[i32] = &[
+    -10, 15, 40, 65, 90, 115, 140, 165, 190, 215, 240, 290, 340, 420,
+];
+const FC0012_GAINS: &[i32] = &[-99, -40, 71, 179, 192];
+const FC0013_GAINS: &[i32] = &[
+    -99, -73, -65, -63, -60, -58, -54, 58, 61, 63, 65, 67, 68, 70, 71, 179,
+    181, 182, 184, 186, 188, 191, 197,
+];
+const R82XX_GAINS: &[i32] = &[
+    0, 9, 14, 27, 37, 77, 87, 125, 144, 157, 166, 197, 207, 229, 254, 280,
+    297, 328, 338, 364, 372, 386, 402, 421, 434, 439, 445, 480, 496,
+];
+// librtlsdr doesn't list steps for these, so we can't either
+const NO_GAINS: &[i32] = &[];
+
+impl TunerType {
+    pub fn from_u32(v: u32) -> Self {
+        match v {
+            1 => TunerType::E4000,
+            2 => TunerType::Fc0012,
+            3 => TunerType::Fc0013,
+            4 => TunerType::Fc2580,
+            5 => TunerType::R820T,
+            6 => TunerType::R828D,
+            _ => TunerType::Unknown,
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            TunerType::Unknown => 0,
+            TunerType::E4000 => 1,
+            TunerType::Fc0012 => 2,
+            TunerType::Fc0013 => 3,
+            TunerType::Fc2580 => 4,
+            TunerType::R820T => 5,
+            TunerType::R828D => 6,
+        }
+    }
+
+    // valid gain steps, in 10ths of dB. empty if unknown
+    pub fn gains(&self) -> &'static [i32] {
+        match self {
+            TunerType::E4000 => E4000_GAINS,
+            TunerType::Fc0012 => FC0012_GAINS,
+            TunerType::Fc0013 => FC0013_GAINS,
+            TunerType::R820T | TunerType::R828D => R82XX_GAINS,
+            TunerType::Fc2580 | TunerType::Unknown => NO_GAINS,
+        }
+    }
+}
+
+// the 12 byte header an rtl_tcp server sends on connect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DongleInfo {
+    pub tuner: TunerType,
+    pub gain_count: u32,
+}
+
+impl DongleInfo {
+    pub fn new(tuner: TunerType) -> Self {
+        DongleInfo {
+            tuner,
+            gain_count: tuner.gains().len() as u32,
+        }
+    }
+
+    pub fn parse(id: &[u8; 12]) -> Result<Self> {
+        if &id[..4] != b"RTL0" {
+            return Err(Error::BadHeader(*id));
+        }
+        Ok(DongleInfo {
+            tuner: TunerType::from_u32(BigEndian::read_u32(&id[4..8])),
+            gain_count: BigEndian::read_u32(&id[8..12]),
+        })
+    }
+
+    pub fn encode(&self) -> [u8; 12] {
+        let mut id = [0; 12];
+        id[..4].copy_from_slice(b"RTL0");
+        BigEndian::write_u32(&mut id[4..8], self.tuner.to_u32());
+        BigEndian::write_u32(&mut id[8..12], self.gain_count);
+        id
+    }
+
+    pub fn gains(&self) -> &'static [i32] {
+        self.tuner.gains()
+    }
+
+    // the closest valid gain step to gain, in dB, as 10ths of dB. if
+    // the steps are unknown, gain goes through as is, for the tuner to
+    // round
+    pub fn nearest_gain(&self, gain: f32) -> i32 {
+        let tenths = (gain * 10.0).round() as i32;
+        self.gains().iter().cloned()
+            .min_by_key(|g| (g - tenths).abs())
+            .unwrap_or(tenths)
+    }
+
+    // index of the closest valid gain step, for SetTunerGainByIndex.
+    // None if the steps are unknown
+    pub fn nearest_gain_index(&self, gain: f32) -> Option<u32> {
+        let nearest = self.nearest_gain(gain);
+        self.gains().iter().position(|g| *g == nearest).map(|i| i as u32)
+    }
+}
@@ -0,0 +1,93 @@
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+    // the stream ended, whether the server hung up or the connection broke
+    Disconnected(std::io::Error),
+    Resolve(std::io::Error),
+    NoAddress,
+    BadHeader([u8; 12]),
+    BadSampleRate(u32),
+    BadFrequency(u32),
+    BadGain(f32),
+    // the signal's rate can't change once it's streaming
+    LiveSampleRate,
+}
+
+pub type Result<A> = std::result::Result<A, Error>;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use Error::*;
+        match self {
+            Io(e) => write!(f, "rtltcp: {}", e),
+            Disconnected(e) => write!(f, "rtltcp: disconnected: {}", e),
+            Resolve(e) => write!(f, "rtltcp: could not resolve address: {}", e),
+            NoAddress => write!(f, "rtltcp: address resolved to nothing"),
+            BadHeader(id) => write!(f, "rtltcp: bad header: {:?}", id),
+            BadSampleRate(r) => write!(
+                f, "rtltcp: bad sample rate {} (need 225001 - 300000 Hz \
+                    or 900001 - 3200000 Hz)", r),
+            BadFrequency(fr) => write!(f, "rtltcp: bad frequency {} Hz", fr),
+            BadGain(g) => write!(f, "rtltcp: bad gain {} dB", g),
+            LiveSampleRate => write!(
+                f, "rtltcp: can't change sample rate while streaming"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) | Error::Disconnected(e) | Error::Resolve(e) =>
+                Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+// so binaries that return io::Result can still use ?
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        use std::io::ErrorKind;
+        match e {
+            Error::Io(e) | Error::Disconnected(e) | Error::Resolve(e) => e,
+            Error::BadHeader(_) =>
+                std::io::Error::new(ErrorKind::InvalidData, e),
+            other => std::io::Error::new(ErrorKind::InvalidInput, other),
+        }
+    }
+}
+
+// valid rates for the RTL2832U
+pub(super) fn check_rate(rate: u32) -> Result<u32> {
+    if (225001..=300000).contains(&rate) || (900001..=3200000).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(Error::BadSampleRate(rate))
+    }
+}
+
+// generous enough for every tuner. direct sampling goes all the way down,
+// for HF and below
+pub(super) fn check_frequency(frequency: u32) -> Result<u32> {
+    if frequency <= 2200000000 {
+        Ok(frequency)
+    } else {
+        Err(Error::BadFrequency(frequency))
+    }
+}
+
+// every tuner's gain steps fall in here
+pub(super) fn check_gain(gain: f32) -> Result<f32> {
+    if (-10.0..=50.0).contains(&gain) {
+        Ok(gain)
+    } else {
+        Err(Error::BadGain(gain))
+    }
+}
@@ -0,0 +1,405 @@
+use super::signal::Signal;
+
+use std::io::Read;
+use std::net::{SocketAddr, IpAddr, Ipv4Addr, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use byteorder::ReadBytesExt;
+
+mod control;
+pub use control::*;
+
+mod error;
+pub use error::*;
+
+mod dongle;
+pub use dongle::*;
+
+mod server;
+pub use server::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DirectSampling {
+    Off = 0,
+    I = 1,
+    Q = 2,
+}
+
+#[derive(Debug, Clone)]
+pub struct RtlTcp {
+    addr: Vec<SocketAddr>,
+    rate: u32,
+    frequency: u32,
+    gain: Option<f32>,
+    rtlagc: bool,
+    ppm: i32,
+    bias_tee: bool,
+    direct_sampling: DirectSampling,
+    offset_tuning: bool,
+}
+
+impl RtlTcp {
+    pub fn new() -> Self {
+        RtlTcp {
+            addr: vec![
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 1234),
+            ],
+            rate: 1800000,
+            frequency: 100000000,
+            gain: None,
+            rtlagc: false,
+            ppm: 0,
+            bias_tee: false,
+            direct_sampling: DirectSampling::Off,
+            offset_tuning: false,
+        }
+    }
+
+    pub fn address<A: ToSocketAddrs>(mut self, addr: A) -> Result<Self> {
+        self.addr = addr.to_socket_addrs().map_err(Error::Resolve)?.collect();
+        if self.addr.is_empty() {
+            return Err(Error::NoAddress);
+        }
+        Ok(self)
+    }
+
+    pub fn rate(mut self, rate: u32) -> Result<Self> {
+        self.rate = check_rate(rate)?;
+        Ok(self)
+    }
+
+    pub fn frequency(mut self, frequency: u32) -> Result<Self> {
+        self.frequency = check_frequency(frequency)?;
+        Ok(self)
+    }
+
+    // in dB, rounded to the nearest step the tuner has. None means Auto
+    pub fn gain(mut self, gain: Option<f32>) -> Result<Self> {
+        self.gain = gain.map(check_gain).transpose()?;
+        Ok(self)
+    }
+
+    pub fn rtlagc(mut self, rtlagc: bool) -> Self {
+        self.rtlagc = rtlagc;
+        self
+    }
+
+    // frequency correction, in parts per million
+    pub fn ppm(mut self, ppm: i32) -> Self {
+        self.ppm = ppm;
+        self
+    }
+
+    pub fn bias_tee(mut self, bias_tee: bool) -> Self {
+        self.bias_tee = bias_tee;
+        self
+    }
+
+    pub fn direct_sampling(mut self, direct_sampling: DirectSampling) -> Self {
+        self.direct_sampling = direct_sampling;
+        self
+    }
+
+    pub fn offset_tuning(mut self, offset_tuning: bool) -> Self {
+        self.offset_tuning = offset_tuning;
+        self
+    }
+
+    pub fn listen(&self) -> Result<RtlTcpSignal> {
+        Ok(self.connect()?.listen())
+    }
+
+    // the control can retune while the signal keeps streaming
+    pub fn listen_with_control(&self)
+                               -> Result<(RtlTcpSignal, RtlTcpControl)>
+    {
+        let conn = self.connect()?;
+        let control = conn.control();
+        Ok((conn.listen(), control))
+    }
+
+    // reconnects whenever the server goes away. the first connection
+    // has to work, so mistakes show up right away.
+    pub fn listen_reconnecting(&self) -> Result<RtlTcpReconnect> {
+        let signal = self.listen()?;
+        Ok(RtlTcpReconnect::new(self.clone(), signal))
+    }
+
+    fn connect(&self) -> Result<RtlTcpConnection> {
+        let mut conn = RtlTcpConnection::connect(self.rate, &self.addr[..])?;
+        // correction and sampling mode change how frequency is applied,
+        // so they go first
+        if self.ppm != 0 {
+            conn.command(RtlTcpCommand::SetFreqCorrection(self.ppm))?;
+        }
+        if self.direct_sampling != DirectSampling::Off {
+            conn.command(RtlTcpCommand::SetDirectSampling(
+                self.direct_sampling as u32))?;
+        }
+        if self.offset_tuning {
+            conn.command(RtlTcpCommand::SetOffsetTuning(1))?;
+        }
+        conn.command(RtlTcpCommand::SetFrequency(self.frequency))?;
+        if let Some(gain) = self.gain {
+            // manual gain
+            conn.command(RtlTcpCommand::SetTunerGainMode(1))?;
+            let gain = conn.info.nearest_gain(gain);
+            conn.command(RtlTcpCommand::SetTunerGain(gain))?;
+        } else {
+            // automatic gain
+            conn.command(RtlTcpCommand::SetTunerGainMode(0))?;
+        }
+        conn.command(RtlTcpCommand::SetRtlAgc(self.rtlagc as u32))?;
+        if self.bias_tee {
+            conn.command(RtlTcpCommand::SetBiasTee(1))?;
+        }
+        Ok(conn)
+    }
+}
+
+#[derive(Debug)]
+pub struct RtlTcpConnection {
+    pub id: [u8; 12],
+    pub info: DongleInfo,
+    stream: std::io::BufReader<std::net::TcpStream>,
+    writer: Arc<Mutex<std::net::TcpStream>>,
+    // samples RtlTcpSignal has handed out, for the control handles
+    count: Arc<AtomicU64>,
+    rate: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtlTcpCommand {
+    SetFrequency(u32), // Hz
+    SetSampleRate(u32), // Hz
+    SetTunerGainMode(u32), // manual != 0, agc == 0
+    SetTunerGain(i32), // in 10ths of dB, see DongleInfo::gains
+    SetFreqCorrection(i32), // ppm
+    SetIfGain(u16, i16), // stage, in 10ths of dB
+    SetTestMode(u32), // on != 0
+    SetRtlAgc(u32), // AGC_ON != 0
+    SetDirectSampling(u32), // off == 0, I == 1, Q == 2
+    SetOffsetTuning(u32), // on != 0
+    SetRtlXtal(u32), // Hz
+    SetTunerXtal(u32), // Hz
+    SetTunerGainByIndex(u32), // index into DongleInfo::gains
+    SetBiasTee(u32), // on != 0
+}
+
+impl RtlTcpCommand {
+    // command byte and argument, as sent over the wire
+    pub fn encode(&self) -> (u8, u32) {
+        match *self {
+            RtlTcpCommand::SetFrequency(a) => (0x01, a),
+            RtlTcpCommand::SetSampleRate(a) => (0x02, a),
+            RtlTcpCommand::SetTunerGainMode(a) => (0x03, a),
+            RtlTcpCommand::SetTunerGain(a) => (0x04, a as u32),
+            RtlTcpCommand::SetFreqCorrection(a) => (0x05, a as u32),
+            RtlTcpCommand::SetIfGain(stage, gain) =>
+                (0x06, ((stage as u32) << 16) | (gain as u16 as u32)),
+            RtlTcpCommand::SetTestMode(a) => (0x07, a),
+            RtlTcpCommand::SetRtlAgc(a) => (0x08, a),
+            RtlTcpCommand::SetDirectSampling(a) => (0x09, a),
+            RtlTcpCommand::SetOffsetTuning(a) => (0x0a, a),
+            RtlTcpCommand::SetRtlXtal(a) => (0x0b, a),
+            RtlTcpCommand::SetTunerXtal(a) => (0x0c, a),
+            RtlTcpCommand::SetTunerGainByIndex(a) => (0x0d, a),
+            RtlTcpCommand::SetBiasTee(a) => (0x0e, a),
+        }
+    }
+
+    pub fn decode(cmd: u8, arg: u32) -> Option<Self> {
+        match cmd {
+            0x01 => Some(RtlTcpCommand::SetFrequency(arg)),
+            0x02 => Some(RtlTcpCommand::SetSampleRate(arg)),
+            0x03 => Some(RtlTcpCommand::SetTunerGainMode(arg)),
+            0x04 => Some(RtlTcpCommand::SetTunerGain(arg as i32)),
+            0x05 => Some(RtlTcpCommand::SetFreqCorrection(arg as i32)),
+            0x06 => Some(RtlTcpCommand::SetIfGain(
+                (arg >> 16) as u16, arg as u16 as i16)),
+            0x07 => Some(RtlTcpCommand::SetTestMode(arg)),
+            0x08 => Some(RtlTcpCommand::SetRtlAgc(arg)),
+            0x09 => Some(RtlTcpCommand::SetDirectSampling(arg)),
+            0x0a => Some(RtlTcpCommand::SetOffsetTuning(arg)),
+            0x0b => Some(RtlTcpCommand::SetRtlXtal(arg)),
+            0x0c => Some(RtlTcpCommand::SetTunerXtal(arg)),
+            0x0d => Some(RtlTcpCommand::SetTunerGainByIndex(arg)),
+            0x0e => Some(RtlTcpCommand::SetBiasTee(arg)),
+            _ => None,
+        }
+    }
+}
+
+impl RtlTcpConnection {
+    pub fn connect<A: ToSocketAddrs>(rate: u32, addr: A) -> Result<Self> {
+        let rawstream = std::net::TcpStream::connect(addr)?;
+        let writer = Arc::new(Mutex::new(rawstream.try_clone()?));
+        let mut stream = std::io::BufReader::with_capacity(
+            check_rate(rate)? as usize, rawstream);
+        let mut id = [0; 12];
+        stream.read_exact(&mut id)?;
+        let mut us = RtlTcpConnection {
+            stream,
+            writer,
+            count: Arc::new(AtomicU64::new(0)),
+            info: DongleInfo::parse(&id)?,
+            id,
+            rate,
+        };
+        us.command(RtlTcpCommand::SetSampleRate(rate))?;
+        Ok(us)
+    }
+
+    pub fn command(&mut self, cmd: RtlTcpCommand) -> Result<()> {
+        if let RtlTcpCommand::SetSampleRate(rate) = cmd {
+            self.rate = check_rate(rate)?;
+        }
+        control::send(&self.writer, cmd)
+    }
+
+    pub fn control(&self) -> RtlTcpControl {
+        RtlTcpControl::new(self.writer.clone(), self.count.clone(),
+                           self.info)
+    }
+
+    pub fn read(&mut self) -> Result<num::Complex<u8>> {
+        let i = self.stream.read_u8()?;
+        let q = self.stream.read_u8()?;
+        Ok(num::Complex::new(i, q))
+    }
+
+    pub fn listen(self) -> RtlTcpSignal {
+        RtlTcpSignal {
+            rate: self.rate as f32,
+            conn: self,
+            done: false,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RtlTcpSignal {
+    conn: RtlTcpConnection,
+    rate: f32,
+    done: bool,
+    error: Option<Error>,
+}
+
+impl RtlTcpSignal {
+    pub fn control(&self) -> RtlTcpControl {
+        self.conn.control()
+    }
+
+    // why the signal ended, or None if it hasn't. a live server never
+    // runs out, so even a clean hang up is Disconnected. read() on the
+    // connection still tells EOF apart, for servers that do run out
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+}
+
+impl Signal for RtlTcpSignal {
+    type Sample = num::Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        if self.done {
+            return None;
+        }
+        match self.conn.read() {
+            Ok(iq) => {
+                self.conn.count.fetch_add(1, Ordering::Relaxed);
+                Some(num::Complex::new(
+                    (iq.re as f32 - 128.0) / 128.0,
+                    (iq.im as f32 - 128.0) / 128.0,
+                ))
+            },
+            Err(Error::Io(e)) => {
+                self.done = true;
+                self.error = Some(Error::Disconnected(e));
+                None
+            },
+            Err(e) => {
+                self.done = true;
+                self.error = Some(e);
+                None
+            },
+        }
+    }
+    fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+#[derive(Debug)]
+pub struct RtlTcpReconnect {
+    rtl: RtlTcp,
+    signal: Option<RtlTcpSignal>,
+    delay: std::time::Duration,
+    retries: Option<usize>,
+    error: Option<Error>,
+}
+
+impl RtlTcpReconnect {
+    fn new(rtl: RtlTcp, signal: RtlTcpSignal) -> Self {
+        RtlTcpReconnect {
+            rtl,
+            signal: Some(signal),
+            delay: std::time::Duration::from_secs(1),
+            retries: None,
+            error: None,
+        }
+    }
+
+    // how long to wait between attempts
+    pub fn delay(mut self, delay: std::time::Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    // attempts in a row before giving up. None means keep trying forever
+    pub fn retries(mut self, retries: Option<usize>) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    // the most recent reason we lost the server
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    // only good until the next reconnect, and None while reconnecting
+    pub fn control(&self) -> Option<RtlTcpControl> {
+        self.signal.as_ref().map(|s| s.control())
+    }
+}
+
+impl Signal for RtlTcpReconnect {
+    type Sample = num::Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        let mut attempts = 0;
+        loop {
+            if let Some(ref mut signal) = self.signal {
+                if let Some(v) = signal.next() {
+                    return Some(v);
+                }
+                self.error = signal.error.take();
+                self.signal = None;
+            } else {
+                if self.retries.map(|r| attempts >= r).unwrap_or(false) {
+                    return None;
+                }
+                if attempts > 0 {
+                    std::thread::sleep(self.delay);
+                }
+                attempts += 1;
+                match self.rtl.listen() {
+                    Ok(signal) => self.signal = Some(signal),
+                    Err(e) => self.error = Some(e),
+                }
+            }
+        }
+    }
+    fn rate(&self) -> f32 {
+        self.rtl.rate as f32
+    }
+}
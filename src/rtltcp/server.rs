@@ -0,0 +1,138 @@
+use super::{DongleInfo, Result, RtlTcpCommand, TunerType};
+use crate::signal::{IqFormat, Nco, Signal};
+
+use std::io::{ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use byteorder::{BigEndian, ReadBytesExt};
+use num::Complex;
+
+// samples per write
+const CHUNK: usize = 8192;
+
+#[derive(Debug)]
+pub struct RtlTcpServer<S> {
+    listener: TcpListener,
+    signal: S,
+    frequency: u32,
+    throttle: bool,
+}
+
+impl<S> RtlTcpServer<S> where S: Signal<Sample=Complex<f32>> {
+    // frequency is what the signal is centered on, in Hz. use port 0
+    // to pick any free port, and find it again with local_addr()
+    pub fn bind<A: ToSocketAddrs>(addr: A, signal: S, frequency: u32)
+                                  -> Result<Self>
+    {
+        Ok(RtlTcpServer {
+            listener: TcpListener::bind(addr)?,
+            signal,
+            frequency,
+            throttle: false,
+        })
+    }
+
+    // send no faster than real time, like real hardware
+    pub fn throttle(mut self, throttle: bool) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    // serves a single client, until it hangs up or the signal ends.
+    // the signal rate is fixed, so SetSampleRate is ignored.
+    pub fn serve(mut self) -> Result<()> {
+        let (stream, _) = self.listener.accept()?;
+        let tuned = Arc::new(AtomicU32::new(self.frequency));
+        let commands = stream.try_clone()?;
+        let tunedc = tuned.clone();
+        std::thread::spawn(move || read_commands(commands, &tunedc));
+
+        let result = self.stream(&stream, &tuned);
+        // also stops the command thread
+        stream.shutdown(Shutdown::Both).ok();
+        match result {
+            Err(ref e) if hung_up(e) => Ok(()),
+            r => Ok(r?),
+        }
+    }
+
+    fn stream(&mut self, mut stream: &TcpStream, tuned: &AtomicU32)
+              -> std::io::Result<()>
+    {
+        // claim to be the most common dongle
+        stream.write_all(&DongleInfo::new(TunerType::R820T).encode())?;
+
+        let rate = self.signal.rate() as f64;
+        let start = std::time::Instant::now();
+        let mut sent = 0;
+        let mut nco = Nco::new(self.signal.rate(), 0.0);
+        let mut buf = Vec::with_capacity(CHUNK * IqFormat::Cu8.size());
+        loop {
+            // tuning above the signal's center moves it down. subtract
+            // first, f32 can't hold 100MHz to the hertz
+            let tuned = tuned.load(Ordering::Relaxed) as i64;
+            nco.set_frequency((self.frequency as i64 - tuned) as f32);
+
+            buf.clear();
+            for _ in 0..CHUNK {
+                let v = match self.signal.next() {
+                    Some(v) => v,
+                    None => break,
+                };
+                IqFormat::Cu8.write(&mut buf, nco.mix(v))?;
+            }
+            if buf.is_empty() {
+                return Ok(());
+            }
+            stream.write_all(&buf)?;
+
+            sent += buf.len() / IqFormat::Cu8.size();
+            if self.throttle {
+                let due = std::time::Duration::from_secs_f64(
+                    sent as f64 / rate);
+                if let Some(wait) = due.checked_sub(start.elapsed()) {
+                    std::thread::sleep(wait);
+                }
+            }
+        }
+    }
+}
+
+impl<S> RtlTcpServer<S>
+where
+    S: Signal<Sample=Complex<f32>> + Send + 'static,
+{
+    pub fn spawn(self) -> std::thread::JoinHandle<Result<()>> {
+        std::thread::spawn(move || self.serve())
+    }
+}
+
+fn hung_up(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::BrokenPipe
+             | ErrorKind::ConnectionReset
+             | ErrorKind::ConnectionAborted)
+}
+
+// runs until the client hangs up
+fn read_commands(mut stream: TcpStream, tuned: &AtomicU32) {
+    loop {
+        let cmd = match stream.read_u8() {
+            Ok(cmd) => cmd,
+            Err(_) => return,
+        };
+        let arg = match stream.read_u32::<BigEndian>() {
+            Ok(arg) => arg,
+            Err(_) => return,
+        };
+        if let Some(RtlTcpCommand::SetFrequency(f)) =
+            RtlTcpCommand::decode(cmd, arg)
+        {
+            tuned.store(f, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use super::signal::{IqFormat, Signal};
+
+use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Result, Write};
+use std::path::{Path, PathBuf};
+use num::Complex;
+use serde_json::{Map, Value};
+
+const VERSION: &str = "1.0.0";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Datatype {
+    Cu8,
+    Ci8,
+    Ci16Le,
+    Cf32Le,
+}
+
+impl Datatype {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cu8" => Some(Datatype::Cu8),
+            "ci8" => Some(Datatype::Ci8),
+            "ci16_le" => Some(Datatype::Ci16Le),
+            "cf32_le" => Some(Datatype::Cf32Le),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Datatype::Cu8 => "cu8",
+            Datatype::Ci8 => "ci8",
+            Datatype::Ci16Le => "ci16_le",
+            Datatype::Cf32Le => "cf32_le",
+        }
+    }
+
+    pub fn format(&self) -> IqFormat {
+        match self {
+            Datatype::Cu8 => IqFormat::Cu8,
+            Datatype::Ci8 => IqFormat::Cs8,
+            Datatype::Ci16Le => IqFormat::Cs16,
+            Datatype::Cf32Le => IqFormat::Cf32,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotation {
+    pub sample_start: u64,
+    pub sample_count: Option<u64>,
+    // in Hz
+    pub freq_lower_edge: Option<f64>,
+    pub freq_upper_edge: Option<f64>,
+    pub label: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl Annotation {
+    fn from_json(value: &Value) -> Result<Self> {
+        let f64_field = |name| value.get(name).and_then(Value::as_f64);
+        let str_field = |name| value.get(name).and_then(Value::as_str)
+            .map(|s: &str| s.to_owned());
+        Ok(Annotation {
+            sample_start: value.get("core:sample_start")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| invalid("annotation missing core:sample_start"))?,
+            sample_count: value.get("core:sample_count")
+                .and_then(Value::as_u64),
+            freq_lower_edge: f64_field("core:freq_lower_edge"),
+            freq_upper_edge: f64_field("core:freq_upper_edge"),
+            label: str_field("core:label"),
+            comment: str_field("core:comment"),
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        let mut obj = Map::new();
+        obj.insert("core:sample_start".to_owned(), self.sample_start.into());
+        if let Some(count) = self.sample_count {
+            obj.insert("core:sample_count".to_owned(), count.into());
+        }
+        if let Some(lower) = self.freq_lower_edge {
+            obj.insert("core:freq_lower_edge".to_owned(), lower.into());
+        }
+        if let Some(upper) = self.freq_upper_edge {
+            obj.insert("core:freq_upper_edge".to_owned(), upper.into());
+        }
+        if let Some(ref label) = self.label {
+            obj.insert("core:label".to_owned(), label.clone().into());
+        }
+        if let Some(ref comment) = self.comment {
+            obj.insert("core:comment".to_owned(), comment.clone().into());
+        }
+        obj.into()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metadata {
+    pub datatype: Datatype,
+    pub rate: f32,
+    // center frequency of the first capture, in Hz
+    pub frequency: Option<f64>,
+    // ISO 8601, as written in the file
+    pub datetime: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub hw: Option<String>,
+    pub annotations: Vec<Annotation>,
+}
+
+impl Metadata {
+    pub fn new(datatype: Datatype, rate: f32) -> Self {
+        Metadata {
+            datatype,
+            rate,
+            frequency: None,
+            datetime: None,
+            description: None,
+            author: None,
+            hw: None,
+            annotations: vec![],
+        }
+    }
+
+    pub fn read<R: Read>(r: R) -> Result<Self> {
+        let value: Value = serde_json::from_reader(r)?;
+        let global = value.get("global")
+            .ok_or_else(|| invalid("missing global"))?;
+        let str_field = |name| global.get(name).and_then(Value::as_str)
+            .map(|s: &str| s.to_owned());
+
+        let datatype = global.get("core:datatype")
+            .ok_or_else(|| invalid("missing core:datatype"))?
+            .as_str()
+            .and_then(Datatype::from_name)
+            .ok_or_else(|| invalid("unsupported core:datatype"))?;
+        let rate = global.get("core:sample_rate")
+            .and_then(Value::as_f64)
+            .ok_or_else(|| invalid("missing core:sample_rate"))?;
+
+        // we only look at the capture at the start of the file
+        let capture = value.get("captures")
+            .and_then(Value::as_array)
+            .and_then(|c| c.iter().find(|c| {
+                c.get("core:sample_start").and_then(Value::as_u64)
+                    .unwrap_or(0) == 0
+            }));
+
+        let mut annotations = vec![];
+        if let Some(list) = value.get("annotations").and_then(Value::as_array) {
+            for a in list {
+                annotations.push(Annotation::from_json(a)?);
+            }
+        }
+
+        Ok(Metadata {
+            datatype,
+            rate: rate as f32,
+            frequency: capture.and_then(|c| c.get("core:frequency"))
+                .and_then(Value::as_f64),
+            datetime: capture.and_then(|c| c.get("core:datetime"))
+                .and_then(Value::as_str)
+                .map(|s| s.to_owned()),
+            description: str_field("core:description"),
+            author: str_field("core:author"),
+            hw: str_field("core:hw"),
+            annotations,
+        })
+    }
+
+    pub fn write<W: Write>(&self, w: W) -> Result<()> {
+        let mut global = Map::new();
+        global.insert("core:datatype".to_owned(), self.datatype.name().into());
+        global.insert("core:sample_rate".to_owned(), (self.rate as f64).into());
+        global.insert("core:version".to_owned(), VERSION.into());
+        if let Some(ref description) = self.description {
+            global.insert("core:description".to_owned(),
+                          description.clone().into());
+        }
+        if let Some(ref author) = self.author {
+            global.insert("core:author".to_owned(), author.clone().into());
+        }
+        if let Some(ref hw) = self.hw {
+            global.insert("core:hw".to_owned(), hw.clone().into());
+        }
+
+        let mut capture = Map::new();
+        capture.insert("core:sample_start".to_owned(), 0u64.into());
+        if let Some(frequency) = self.frequency {
+            capture.insert("core:frequency".to_owned(), frequency.into());
+        }
+        if let Some(ref datetime) = self.datetime {
+            capture.insert("core:datetime".to_owned(), datetime.clone().into());
+        }
+
+        let mut meta = Map::new();
+        meta.insert("global".to_owned(), global.into());
+        meta.insert("captures".to_owned(), vec![Value::from(capture)].into());
+        meta.insert("annotations".to_owned(), self.annotations.iter()
+                    .map(Annotation::to_json).collect::<Vec<_>>().into());
+        serde_json::to_writer_pretty(w, &Value::from(meta))?;
+        Ok(())
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("sigmf: {}", msg))
+}
+
+// accepts foo, foo.sigmf-meta, or foo.sigmf-data
+fn paths(path: &Path) -> (PathBuf, PathBuf) {
+    let base = match path.extension().and_then(|e| e.to_str()) {
+        Some("sigmf-meta") | Some("sigmf-data") => path.with_extension(""),
+        _ => path.to_owned(),
+    };
+    let mut meta = base.clone().into_os_string();
+    meta.push(".sigmf-meta");
+    let mut data = base.into_os_string();
+    data.push(".sigmf-data");
+    (meta.into(), data.into())
+}
+
+// current time, for core:datetime
+fn now() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // civil-from-days, shifted so years start in March
+    let z = days as i64 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day,
+            secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[derive(Debug)]
+pub struct SigMf {
+    meta: Metadata,
+    data: BufReader<std::fs::File>,
+}
+
+impl SigMf {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (meta, data) = paths(path.as_ref());
+        let meta = Metadata::read(BufReader::new(std::fs::File::open(meta)?))?;
+        let data = BufReader::new(std::fs::File::open(data)?);
+        Ok(SigMf {
+            meta,
+            data,
+        })
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.meta
+    }
+
+    pub fn frequency(&self) -> Option<f64> {
+        self.meta.frequency
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.meta.annotations
+    }
+}
+
+impl Signal for SigMf {
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        self.meta.datatype.format().read(&mut self.data).ok()
+    }
+    fn rate(&self) -> f32 {
+        self.meta.rate
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SigMfWriter {
+    datatype: Datatype,
+    frequency: Option<f64>,
+    description: Option<String>,
+    author: Option<String>,
+    hw: Option<String>,
+    annotations: Vec<Annotation>,
+}
+
+impl SigMfWriter {
+    pub fn new(datatype: Datatype) -> Self {
+        SigMfWriter {
+            datatype,
+            frequency: None,
+            description: None,
+            author: None,
+            hw: None,
+            annotations: vec![],
+        }
+    }
+
+    // center frequency, in Hz
+    pub fn frequency(mut self, frequency: f64) -> Self {
+        self.frequency = Some(frequency);
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    pub fn author(mut self, author: &str) -> Self {
+        self.author = Some(author.to_owned());
+        self
+    }
+
+    pub fn hw(mut self, hw: &str) -> Self {
+        self.hw = Some(hw.to_owned());
+        self
+    }
+
+    pub fn annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    // writes the whole signal, so make sure it ends. returns sample count
+    pub fn write<P, S>(&self, path: P, mut signal: S) -> Result<u64>
+    where
+        P: AsRef<Path>,
+        S: Signal<Sample=Complex<f32>>,
+    {
+        let (meta, data) = paths(path.as_ref());
+        let metadata = Metadata {
+            frequency: self.frequency,
+            datetime: Some(now()),
+            description: self.description.clone(),
+            author: self.author.clone(),
+            hw: self.hw.clone(),
+            annotations: self.annotations.clone(),
+            ..Metadata::new(self.datatype, signal.rate())
+        };
+        // metadata first, so a cut-short recording is still readable
+        let mut meta = BufWriter::new(std::fs::File::create(meta)?);
+        metadata.write(&mut meta)?;
+        meta.flush()?;
+
+        let format = self.datatype.format();
+        let mut data = BufWriter::new(std::fs::File::create(data)?);
+        let mut count = 0;
+        while let Some(v) = signal.next() {
+            format.write(&mut data, v)?;
+            count += 1;
+        }
+        data.flush()?;
+        Ok(count)
+    }
+}
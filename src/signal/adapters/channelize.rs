@@ -0,0 +1,159 @@
+use crate::Signal;
+use crate::filter::{FirD, Window};
+
+use num::Complex;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+// transition band, as a fraction of the channel spacing, centered on the
+// channel edge. the outer edges of each channel alias into each other
+const TRANSITION: f32 = 0.2;
+const ATTENUATION: f32 = 80.0;
+
+struct Bank<S> {
+    signal: S,
+    // prototype low-pass, padded to a multiple of the channel count
+    taps: Vec<f32>,
+    history: VecDeque<Complex<f32>>,
+    ifft: Arc<dyn rustfft::FFT<f32>>,
+    branches: Vec<Complex<f32>>,
+    outputs: Vec<Complex<f32>>,
+    // None once that channel is dropped, so nobody waits on it
+    queues: Vec<Option<VecDeque<Complex<f32>>>>,
+    done: bool,
+}
+
+impl<S> Bank<S> where S: Signal<Sample=Complex<f32>> {
+    // one sample for every channel, from one sample per channel
+    fn frame(&mut self) -> bool {
+        let n = self.branches.len();
+        for _ in 0..n {
+            match self.signal.next() {
+                Some(v) => {
+                    self.history.pop_back();
+                    self.history.push_front(v);
+                },
+                None => {
+                    self.done = true;
+                    return false;
+                },
+            }
+        }
+
+        for b in self.branches.iter_mut() {
+            *b = Complex::new(0.0, 0.0);
+        }
+        for (i, (h, x)) in self.taps.iter().zip(self.history.iter())
+            .enumerate()
+        {
+            self.branches[i % n] += x * h;
+        }
+        // channel k sums branch p rotated by e^(2 pi j k p / n)
+        self.ifft.process(&mut self.branches, &mut self.outputs);
+        for (queue, v) in self.queues.iter_mut().zip(self.outputs.iter()) {
+            if let Some(queue) = queue {
+                queue.push_back(*v);
+            }
+        }
+        true
+    }
+}
+
+impl<S> std::fmt::Debug for Bank<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Bank")
+            .field("channels", &self.branches.len())
+            .field("taps", &self.taps.len())
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+// one output of Signal::channelize. all the channels share one filter
+// bank, and whichever is read first does the work for the rest. reading
+// some channels and not others will buffer without bound, so drop any
+// channel you don't need
+#[derive(Debug)]
+pub struct Channel<S> {
+    bank: Arc<Mutex<Bank<S>>>,
+    index: usize,
+    rate: f32,
+    offset: f32,
+}
+
+impl<S> Channel<S> where S: Signal<Sample=Complex<f32>> {
+    pub(crate) fn split(signal: S, channels: usize) -> Vec<Self> {
+        let n = channels.max(1);
+        let input = signal.rate();
+        let spacing = input / n as f32;
+
+        let window = Window::Kaiser(TRANSITION * spacing, ATTENUATION);
+        let mut taps = FirD::LowPass(spacing / 2.0, window).taps(input);
+        let len = (taps.len() + n - 1) / n * n;
+        taps.resize(len, 0.0);
+
+        let bank = Bank {
+            signal,
+            history: std::iter::repeat(Complex::new(0.0, 0.0))
+                .take(len).collect(),
+            taps,
+            ifft: rustfft::FFTplanner::new(true).plan_fft(n),
+            branches: vec![Complex::new(0.0, 0.0); n],
+            outputs: vec![Complex::new(0.0, 0.0); n],
+            queues: vec![Some(VecDeque::new()); n],
+            done: false,
+        };
+        let bank = Arc::new(Mutex::new(bank));
+
+        (0..n).map(|index| {
+            // the upper half of the channels are negative frequencies
+            let k = if index <= n / 2 {
+                index as f32
+            } else {
+                index as f32 - n as f32
+            };
+            Channel {
+                bank: bank.clone(),
+                index,
+                rate: spacing,
+                offset: k * spacing,
+            }
+        }).collect()
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // center of this channel, relative to the center of the input, in Hz
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+}
+
+impl<S> Signal for Channel<S> where S: Signal<Sample=Complex<f32>> {
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        let mut bank = self.bank.lock().unwrap();
+        loop {
+            let queue = bank.queues[self.index].as_mut().unwrap();
+            if let Some(v) = queue.pop_front() {
+                return Some(v);
+            }
+            if bank.done || !bank.frame() {
+                return None;
+            }
+        }
+    }
+    fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+impl<S> Drop for Channel<S> {
+    fn drop(&mut self) {
+        if let Ok(mut bank) = self.bank.lock() {
+            bank.queues[self.index] = None;
+        }
+    }
+}
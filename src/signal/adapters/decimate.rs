@@ -0,0 +1,258 @@
+use crate::Signal;
+use crate::filter::{Convolve, FirD, Window};
+
+use std::collections::VecDeque;
+
+// fraction of the output nyquist rate that is kept clean
+const BANDWIDTH: f32 = 0.8;
+// stopband attenuation for every stage, in dB
+const ATTENUATION: f32 = 80.0;
+// order of the CIC
+const CIC_ORDER: i32 = 6;
+// factors at least this big use a CIC, if they are even
+const CIC_MIN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DecimateMode {
+    // pick from the below, based on the factor
+    Auto,
+    // a cascade of half-band filters. power-of-two factors only
+    HalfBand,
+    // a CIC, then a compensating FIR that decimates by 2. even factors only
+    Cic,
+    // a single FIR, evaluated only at the output rate
+    Polyphase,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecimateError {
+    // the output rate does not divide the input rate
+    NotInteger(f32, f32),
+    // the output rate is higher than the input rate
+    Upsampling(f32, f32),
+    // the factor does not fit the mode
+    BadFactor(DecimateMode, usize),
+}
+
+impl std::fmt::Display for DecimateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecimateError::NotInteger(from, to) =>
+                write!(f, "decimate: {} is not a divisor of {}", to, from),
+            DecimateError::Upsampling(from, to) =>
+                write!(f, "decimate: {} is above the input rate {}", to, from),
+            DecimateError::BadFactor(mode, factor) =>
+                write!(f, "decimate: {:?} can't decimate by {}", mode, factor),
+        }
+    }
+}
+
+impl std::error::Error for DecimateError {}
+
+// a decimating FIR, that only computes the samples it keeps
+#[derive(Clone, Debug)]
+struct Stage<A> {
+    // non-zero taps only, with their delay
+    taps: Vec<(usize, f32)>,
+    history: VecDeque<A>,
+    factor: usize,
+    count: usize,
+}
+
+impl<A> Stage<A> where A: Convolve<f32> {
+    fn new(taps: Vec<f32>, factor: usize) -> Self {
+        Stage {
+            history: std::iter::repeat(A::zero()).take(taps.len()).collect(),
+            taps: taps.into_iter().enumerate()
+                .filter(|(_, t)| t.abs() > 1e-7).collect(),
+            factor,
+            count: 0,
+        }
+    }
+
+    fn push(&mut self, value: A) -> Option<A> {
+        self.history.pop_back();
+        self.history.push_front(value);
+        self.count += 1;
+        if self.count < self.factor {
+            return None;
+        }
+        self.count = 0;
+
+        let mut accum = A::zero();
+        for &(i, ref t) in self.taps.iter() {
+            accum.accumulate(&self.history[i], t);
+        }
+        Some(accum)
+    }
+}
+
+// a half-band filter at rate, protecting everything below pass
+fn half_band(rate: f32, pass: f32) -> Vec<f32> {
+    let window = Window::Kaiser(rate / 2.0 - 2.0 * pass, ATTENUATION);
+    FirD::LowPass(rate / 4.0, window).taps(rate)
+}
+
+// the CIC, in its non-recursive form since float integrators drift: one
+// stage of (1 + z^-1 + ... + z^-(p-1))^N per prime factor p
+fn cic(factor: usize) -> Vec<(Vec<f32>, usize)> {
+    let mut stages = vec![];
+    let mut rest = factor;
+    let mut p = 2;
+    while rest > 1 {
+        while rest % p == 0 {
+            let mut taps = vec![1.0];
+            for _ in 0..CIC_ORDER {
+                let mut next = vec![0.0; taps.len() + p - 1];
+                for (i, t) in taps.iter().enumerate() {
+                    for n in next[i..i + p].iter_mut() {
+                        *n += t / p as f32;
+                    }
+                }
+                taps = next;
+            }
+            stages.push((taps, p));
+            rest /= p;
+        }
+        p += 1;
+    }
+    stages
+}
+
+// low-pass at rate with the CIC droop taken back out of the passband,
+// by integrating the desired response directly, then windowing
+fn cic_compensation(cic: usize, rate: f32, pass: f32, stop: f32) -> Vec<f32> {
+    use std::f32::consts::PI;
+    let window = Window::Kaiser(stop - pass, ATTENUATION).coefficients(rate);
+    let n = window.len();
+    let center = (n / 2) as f32;
+    let cutoff = (pass + stop) / 2.0 / rate;
+
+    let droop = |f: f32| {
+        if f == 0.0 {
+            1.0
+        } else {
+            let r = cic as f32;
+            ((PI * f).sin() / (r * (PI * f / r).sin())).abs()
+                .powi(CIC_ORDER)
+        }
+    };
+
+    let steps = 1024;
+    let df = cutoff / steps as f32;
+    let mut h: Vec<f32> = window.iter().enumerate().map(|(i, w)| {
+        let x = i as f32 - center;
+        let integral: f32 = (0..steps).map(|k| {
+            let f = (k as f32 + 0.5) * df;
+            (2.0 * PI * f * x).cos() / droop(f)
+        }).sum();
+        2.0 * integral * df * w
+    }).collect();
+    let sum: f32 = h.iter().sum();
+    for v in h.iter_mut() {
+        *v /= sum;
+    }
+    h
+}
+
+#[derive(Clone, Debug)]
+pub struct Decimator<S: Signal> {
+    signal: S,
+    stages: Vec<Stage<S::Sample>>,
+    factor: usize,
+    mode: DecimateMode,
+}
+
+impl<S> Decimator<S> where S: Signal, S::Sample: Convolve<f32> {
+    pub(crate) fn new(signal: S, mode: DecimateMode, rate: f32)
+                      -> Result<Self, DecimateError>
+    {
+        let from = signal.rate();
+        let ratio = from / rate;
+        if ratio < 1.0 - 1e-6 {
+            return Err(DecimateError::Upsampling(from, rate));
+        }
+        let factor = ratio.round() as usize;
+        if (ratio - factor as f32).abs() > 1e-4 * ratio {
+            return Err(DecimateError::NotInteger(from, rate));
+        }
+
+        let mode = match mode {
+            DecimateMode::Auto if factor.is_power_of_two() =>
+                DecimateMode::HalfBand,
+            DecimateMode::Auto if factor >= CIC_MIN && factor % 2 == 0 =>
+                DecimateMode::Cic,
+            DecimateMode::Auto => DecimateMode::Polyphase,
+            DecimateMode::HalfBand if !factor.is_power_of_two() =>
+                return Err(DecimateError::BadFactor(mode, factor)),
+            DecimateMode::Cic if factor < 4 || factor % 2 != 0 =>
+                return Err(DecimateError::BadFactor(mode, factor)),
+            m => m,
+        };
+
+        let out = from / factor as f32;
+        let pass = BANDWIDTH * out / 2.0;
+        let mut stages = vec![];
+        if factor > 1 {
+            match mode {
+                DecimateMode::HalfBand => {
+                    let mut r = from;
+                    while r > out * 1.5 {
+                        stages.push(Stage::new(half_band(r, pass), 2));
+                        r /= 2.0;
+                    }
+                },
+                DecimateMode::Cic => {
+                    for (taps, p) in cic(factor / 2) {
+                        stages.push(Stage::new(taps, p));
+                    }
+                    let taps = cic_compensation(
+                        factor / 2, out * 2.0, pass, out / 2.0);
+                    stages.push(Stage::new(taps, 2));
+                },
+                _ => {
+                    let window = Window::Kaiser(out / 2.0 - pass,
+                                                ATTENUATION);
+                    let cutoff = (pass + out / 2.0) / 2.0;
+                    let taps = FirD::LowPass(cutoff, window).taps(from);
+                    stages.push(Stage::new(taps, factor));
+                },
+            }
+        }
+
+        Ok(Decimator {
+            signal,
+            stages,
+            factor,
+            mode,
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    // never Auto, this is what Auto picked
+    pub fn mode(&self) -> DecimateMode {
+        self.mode
+    }
+}
+
+impl<S> Signal for Decimator<S> where S: Signal, S::Sample: Convolve<f32> {
+    type Sample = S::Sample;
+    fn next(&mut self) -> Option<Self::Sample> {
+        'input: loop {
+            let mut v = self.signal.next()?;
+            for stage in self.stages.iter_mut() {
+                match stage.push(v) {
+                    Some(out) => v = out,
+                    None => continue 'input,
+                }
+            }
+            return Some(v);
+        }
+    }
+    fn rate(&self) -> f32 {
+        self.signal.rate() / self.factor as f32
+    }
+}
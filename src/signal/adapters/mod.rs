@@ -7,25 +7,50 @@ use std::collections::VecDeque;
 mod block;
 pub use block::*;
 
+mod channelize;
+pub use channelize::*;
+
+mod decimate;
+pub use decimate::*;
+
+mod polyphase;
+pub use polyphase::*;
+
+mod record;
+pub use record::*;
+
 mod resample;
 pub use resample::*;
 
+mod shift;
+pub use shift::*;
+
+mod squelch;
+pub use squelch::*;
+
+mod symbolsync;
+pub use symbolsync::*;
+
+mod wbfm;
+pub use wbfm::*;
+
+// keeps every nth sample, with no filtering at all
 #[derive(Debug, Clone)]
-pub struct Decimate<S> {
+pub struct Downsample<S> {
     wait: usize,
     signal: S,
 }
 
-impl<S> Decimate<S> where S: Signal {
+impl<S> Downsample<S> where S: Signal {
     pub(super) fn new(signal: S, rate: f32) -> Self {
-        Decimate {
-            wait: (signal.rate() / rate).round() as usize,
+        Downsample {
+            wait: ((signal.rate() / rate).round() as usize).max(1),
             signal,
         }
     }
 }
 
-impl<S> Signal for Decimate<S> where S: Signal {
+impl<S> Signal for Downsample<S> where S: Signal {
     type Sample = S::Sample;
     fn next(&mut self) -> Option<Self::Sample> {
         for _ in 0..(self.wait - 1) {
@@ -36,7 +61,7 @@ impl<S> Signal for Decimate<S> where S: Signal {
         self.signal.next()
     }
     fn rate(&self) -> f32 {
-        self.signal.rate()
+        self.signal.rate() / self.wait as f32
     }
 }
 
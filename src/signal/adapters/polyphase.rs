@@ -0,0 +1,99 @@
+use crate::Signal;
+use crate::filter::{Convolve, FirD, Window};
+use crate::resample::Polyphase;
+
+use num::Zero;
+use std::collections::VecDeque;
+
+// largest interpolation or decimation factor we will consider
+const MAX_FACTOR: u64 = 1000;
+
+#[derive(Clone, Debug)]
+pub struct PolyphaseResample<S: Signal> {
+    signal: S,
+    up: usize,
+    down: usize,
+    // phases[p][k] is prototype tap k * up + p
+    phases: Vec<Vec<f32>>,
+    history: VecDeque<S::Sample>,
+    phase: usize,
+    started: bool,
+}
+
+impl<S> PolyphaseResample<S> where S: Signal, S::Sample: Convolve<f32> {
+    pub(crate) fn new(signal: S, design: Polyphase, rate: f32) -> Self {
+        let (up, down) = Polyphase::ratio(signal.rate(), rate, MAX_FACTOR);
+        let (up, down) = (up as usize, down as usize);
+
+        // the prototype runs at the upsampled rate
+        let high = signal.rate() * up as f32;
+        let nyquist = signal.rate().min(signal.rate() * up as f32
+                                        / down as f32) / 2.0;
+        // leave some transition band, or the window never ends
+        let width = (1.0 - design.bandwidth.min(0.99)) * nyquist;
+        let cutoff = nyquist - width / 2.0;
+        let window = Window::Kaiser(width, design.attenuation);
+        let taps = FirD::LowPass(cutoff, window).taps(high);
+
+        let per_phase = (taps.len() + up - 1) / up;
+        let phases = (0..up).map(|p| {
+            (0..per_phase).map(|k| {
+                // unity gain needs the zero-stuffed samples made up for
+                taps.get(k * up + p).map(|t| t * up as f32).unwrap_or(0.0)
+            }).collect()
+        }).collect();
+
+        PolyphaseResample {
+            signal,
+            up,
+            down,
+            phases,
+            history: std::iter::repeat(S::Sample::zero())
+                .take(per_phase).collect(),
+            phase: 0,
+            started: false,
+        }
+    }
+
+    // interpolation and decimation factors
+    pub fn factors(&self) -> (usize, usize) {
+        (self.up, self.down)
+    }
+
+    fn push(&mut self) -> Option<()> {
+        let v = self.signal.next()?;
+        self.history.pop_back();
+        self.history.push_front(v);
+        Some(())
+    }
+}
+
+impl<S> Signal for PolyphaseResample<S>
+where
+    S: Signal,
+    S::Sample: Convolve<f32>,
+{
+    type Sample = S::Sample;
+    fn next(&mut self) -> Option<Self::Sample> {
+        if !self.started {
+            self.push()?;
+            self.started = true;
+        } else {
+            self.phase += self.down;
+            while self.phase >= self.up {
+                self.phase -= self.up;
+                self.push()?;
+            }
+        }
+
+        let mut accum = S::Sample::zero();
+        for (c, v) in self.phases[self.phase].iter().zip(self.history.iter()) {
+            accum.accumulate(v, c);
+        }
+        Some(accum)
+    }
+    fn rate(&self) -> f32 {
+        // exact only if the ratio was found
+        self.signal.rate() * self.up as f32 / self.down as f32
+    }
+}
@@ -0,0 +1,59 @@
+use crate::Signal;
+use crate::signal::IqFormat;
+
+use std::io::Write;
+use num::Complex;
+
+#[derive(Debug)]
+pub struct Record<S, W> {
+    signal: S,
+    writer: Option<W>,
+    format: IqFormat,
+    error: Option<std::io::Error>,
+}
+
+impl<S, W> Record<S, W>
+where
+    S: Signal<Sample=Complex<f32>>,
+    W: Write,
+{
+    pub(crate) fn new(signal: S, writer: W, format: IqFormat) -> Self {
+        Record {
+            signal,
+            writer: Some(writer),
+            format,
+            error: None,
+        }
+    }
+
+    // why recording stopped, if it did
+    pub fn error(&self) -> Option<&std::io::Error> {
+        self.error.as_ref()
+    }
+}
+
+impl<S, W> Signal for Record<S, W>
+where
+    S: Signal<Sample=Complex<f32>>,
+    W: Write,
+{
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        let v = self.signal.next();
+        let result = match (v, self.writer.as_mut()) {
+            (Some(v), Some(w)) => self.format.write(w, v),
+            (None, Some(w)) => w.flush(),
+            _ => Ok(()),
+        };
+        // a full disk shouldn't take down a live session, so stop
+        // recording but keep the samples flowing
+        if let Err(e) = result {
+            self.writer = None;
+            self.error = Some(e);
+        }
+        v
+    }
+    fn rate(&self) -> f32 {
+        self.signal.rate()
+    }
+}
@@ -1,5 +1,35 @@
 use crate::Signal;
+use crate::filter::Convolve;
 use crate::resample;
+use super::PolyphaseResample;
+
+// the ways resample_with can do its work
+pub trait ResampleMode<S: Signal> {
+    type Signal: Signal<Sample=S::Sample>;
+    fn resample(self, signal: S, rate: f32) -> Self::Signal;
+}
+
+impl<S> ResampleMode<S> for resample::ConverterType
+where
+    S: Signal,
+    S::Sample: resample::Resample,
+{
+    type Signal = Resample<S>;
+    fn resample(self, signal: S, rate: f32) -> Self::Signal {
+        Resample::new(signal, self, rate)
+    }
+}
+
+impl<S> ResampleMode<S> for resample::Polyphase
+where
+    S: Signal,
+    S::Sample: Convolve<f32>,
+{
+    type Signal = PolyphaseResample<S>;
+    fn resample(self, signal: S, rate: f32) -> Self::Signal {
+        PolyphaseResample::new(signal, self, rate)
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Resample<S: Signal> {
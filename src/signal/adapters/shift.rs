@@ -0,0 +1,61 @@
+use crate::Signal;
+use super::super::Nco;
+
+use num::Complex;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// retunes a running Shift, even after it's buried in other adapters
+#[derive(Clone, Debug)]
+pub struct ShiftControl {
+    // f32 bits, in Hz
+    frequency: Arc<AtomicU32>,
+}
+
+impl ShiftControl {
+    pub fn frequency(&self) -> f32 {
+        f32::from_bits(self.frequency.load(Ordering::Relaxed))
+    }
+
+    // positive moves the signal up
+    pub fn set_frequency(&self, frequency: f32) {
+        self.frequency.store(frequency.to_bits(), Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Shift<S> {
+    signal: S,
+    nco: Nco,
+    control: ShiftControl,
+}
+
+impl<S> Shift<S> where S: Signal<Sample=Complex<f32>> {
+    pub(crate) fn new(signal: S, frequency: f32) -> Self {
+        Shift {
+            nco: Nco::new(signal.rate(), frequency),
+            control: ShiftControl {
+                frequency: Arc::new(AtomicU32::new(frequency.to_bits())),
+            },
+            signal,
+        }
+    }
+
+    pub fn control(&self) -> ShiftControl {
+        self.control.clone()
+    }
+}
+
+impl<S> Signal for Shift<S> where S: Signal<Sample=Complex<f32>> {
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        let frequency = self.control.frequency();
+        if frequency != self.nco.frequency() {
+            self.nco.set_frequency(frequency);
+        }
+        self.signal.next().map(|v| self.nco.mix(v))
+    }
+    fn rate(&self) -> f32 {
+        self.signal.rate()
+    }
+}
@@ -0,0 +1,273 @@
+use crate::Signal;
+use crate::filter::{coefficient, Magnitude};
+
+use num::{Complex, Zero};
+
+fn decibels(x: f32) -> f32 {
+    if x > 0.0 {
+        10.0 * x.log10()
+    } else {
+        -200.0
+    }
+}
+
+// what the squelch listens to
+pub trait SquelchDetector<A> {
+    type Output;
+    fn start(&mut self, rate: f32);
+    // a level to compare against the thresholds, and the value to pass
+    // through, if there is one
+    fn detect(&mut self, value: A) -> (f32, Option<Self::Output>);
+    fn silence(&self) -> Self::Output;
+}
+
+// average power, in dB relative to full scale
+#[derive(Clone, Debug)]
+pub struct Power {
+    time: f32,
+    alpha: f32,
+    power: f32,
+}
+
+impl Power {
+    // smoothing time constant, in seconds
+    pub fn new(time: f32) -> Self {
+        Power { time, alpha: 1.0, power: 0.0 }
+    }
+}
+
+impl<A> SquelchDetector<A> for Power where A: Magnitude + Zero {
+    type Output = A;
+    fn start(&mut self, rate: f32) {
+        self.alpha = coefficient(self.time, rate);
+    }
+    fn detect(&mut self, value: A) -> (f32, Option<A>) {
+        self.power += self.alpha * (value.magnitude().powi(2) - self.power);
+        (decibels(self.power), Some(value))
+    }
+    fn silence(&self) -> A {
+        A::zero()
+    }
+}
+
+// estimated signal to noise ratio in dB, by the M2M4 moments method.
+// assumes a constant envelope signal like FM in complex gaussian noise
+#[derive(Clone, Debug)]
+pub struct Snr {
+    time: f32,
+    alpha: f32,
+    m2: f32,
+    m4: f32,
+}
+
+impl Snr {
+    // smoothing time constant, in seconds
+    pub fn new(time: f32) -> Self {
+        Snr { time, alpha: 1.0, m2: 0.0, m4: 0.0 }
+    }
+}
+
+impl SquelchDetector<Complex<f32>> for Snr {
+    type Output = Complex<f32>;
+    fn start(&mut self, rate: f32) {
+        self.alpha = coefficient(self.time, rate);
+    }
+    fn detect(&mut self, value: Complex<f32>)
+              -> (f32, Option<Complex<f32>>)
+    {
+        let p = value.norm_sqr();
+        self.m2 += self.alpha * (p - self.m2);
+        self.m4 += self.alpha * (p * p - self.m4);
+        let signal = (2.0 * self.m2 * self.m2 - self.m4).max(0.0).sqrt();
+        let noise = (self.m2 - signal).max(0.0);
+        let snr = if noise > 0.0 {
+            signal / noise
+        } else if signal > 0.0 {
+            1e20
+        } else {
+            0.0
+        };
+        (decibels(snr), Some(value))
+    }
+    fn silence(&self) -> Complex<f32> {
+        Complex::zero()
+    }
+}
+
+// fraction of recent samples that were Some, from 0 to 1. for the output
+// of a filter like Pll that only has a value while locked
+#[derive(Clone, Debug)]
+pub struct Lock {
+    time: f32,
+    alpha: f32,
+    locked: f32,
+}
+
+impl Lock {
+    // smoothing time constant, in seconds
+    pub fn new(time: f32) -> Self {
+        Lock { time, alpha: 1.0, locked: 0.0 }
+    }
+}
+
+impl<A> SquelchDetector<Option<A>> for Lock where A: Zero {
+    type Output = A;
+    fn start(&mut self, rate: f32) {
+        self.alpha = coefficient(self.time, rate);
+    }
+    fn detect(&mut self, value: Option<A>) -> (f32, Option<A>) {
+        let locked = if value.is_some() { 1.0 } else { 0.0 };
+        self.locked += self.alpha * (locked - self.locked);
+        (self.locked, value)
+    }
+    fn silence(&self) -> A {
+        A::zero()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SquelchMode {
+    // keep the rate, with zeros while closed
+    Silence,
+    // skip samples while closed
+    Drop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SquelchEvent {
+    // with the time of the change, in seconds since the start
+    Open(f32),
+    Close(f32),
+}
+
+#[derive(Clone, Debug)]
+pub struct SquelchDesign<D> {
+    detector: D,
+    open: f32,
+    close: f32,
+    tail: f32,
+    mode: SquelchMode,
+}
+
+impl<D> SquelchDesign<D> {
+    // opens at or above open, closes below close, in the detector's units
+    pub fn new(detector: D, open: f32, close: f32) -> Self {
+        SquelchDesign {
+            detector,
+            open,
+            close,
+            tail: 0.0,
+            mode: SquelchMode::Silence,
+        }
+    }
+
+    // how long to stay open after dropping below close, in seconds
+    pub fn tail(mut self, tail: f32) -> Self {
+        self.tail = tail;
+        self
+    }
+
+    pub fn mode(mut self, mode: SquelchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl SquelchDesign<Power> {
+    pub fn power(open: f32, close: f32) -> Self {
+        Self::new(Power::new(0.05), open, close)
+    }
+}
+
+impl SquelchDesign<Snr> {
+    pub fn snr(open: f32, close: f32) -> Self {
+        Self::new(Snr::new(0.05), open, close)
+    }
+}
+
+impl SquelchDesign<Lock> {
+    pub fn lock() -> Self {
+        Self::new(Lock::new(0.02), 0.9, 0.5)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Squelch<S, D, F> {
+    signal: S,
+    design: SquelchDesign<D>,
+    events: F,
+    tail: usize,
+    remaining: usize,
+    open: bool,
+    count: u64,
+}
+
+impl<S, D, F> Squelch<S, D, F>
+where
+    S: Signal,
+    D: SquelchDetector<S::Sample>,
+    F: FnMut(SquelchEvent),
+{
+    pub(crate) fn new(signal: S, mut design: SquelchDesign<D>, events: F)
+                      -> Self
+    {
+        design.detector.start(signal.rate());
+        Squelch {
+            tail: (design.tail * signal.rate()).round() as usize,
+            remaining: 0,
+            signal,
+            design,
+            events,
+            open: false,
+            count: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+}
+
+impl<S, D, F> Signal for Squelch<S, D, F>
+where
+    S: Signal,
+    D: SquelchDetector<S::Sample>,
+    F: FnMut(SquelchEvent),
+{
+    type Sample = D::Output;
+    fn next(&mut self) -> Option<Self::Sample> {
+        loop {
+            let (level, value) = self.design.detector.detect(
+                self.signal.next()?);
+            let time = self.count as f32 / self.signal.rate();
+            self.count += 1;
+
+            if !self.open && level >= self.design.open {
+                self.open = true;
+                self.remaining = self.tail;
+                (self.events)(SquelchEvent::Open(time));
+            } else if self.open && level < self.design.close {
+                if self.remaining == 0 {
+                    self.open = false;
+                    (self.events)(SquelchEvent::Close(time));
+                } else {
+                    self.remaining -= 1;
+                }
+            } else if self.open {
+                self.remaining = self.tail;
+            }
+
+            if self.open {
+                let detector = &self.design.detector;
+                return Some(value.unwrap_or_else(|| detector.silence()));
+            }
+            if self.design.mode == SquelchMode::Silence {
+                return Some(self.design.detector.silence());
+            }
+        }
+    }
+    fn rate(&self) -> f32 {
+        // not quite true in Drop mode, but it's the best we can say
+        self.signal.rate()
+    }
+}
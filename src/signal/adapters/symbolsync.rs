@@ -0,0 +1,341 @@
+use crate::Signal;
+use crate::filter::{ControlLoop, Pulse};
+
+use num::{Complex, Zero};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+// arms in the polyphase filterbank
+const ARMS: usize = 32;
+
+// Mueller-Muller's slope at lock, for QPSK through a raised cosine g with
+// 0.35 rolloff. the sliced symbols are sqrt(2) long, so it's
+// 2 sqrt(2) |g'(1)|. anywhere from 0.2 to 0.5 rolloff is within 12%
+const MM_GAIN: f32 = 2.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimingDetector {
+    // needs two samples per symbol, and doesn't care about the carrier.
+    // expects matched filtered input
+    Gardner,
+    // needs only one sample per symbol, but decides on each symbol, so
+    // expects matched filtered BPSK or QPSK with the carrier recovered
+    MuellerMuller,
+    // matched filters the input itself with this pulse, spanning this
+    // many symbols, and steers by its derivative
+    Polyphase(Pulse, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolSyncDesign {
+    detector: TimingDetector,
+    symbol_rate: f32,
+    bandwidth: f32,
+    damping: f32,
+    deviation: f32,
+}
+
+impl SymbolSyncDesign {
+    pub fn new(detector: TimingDetector, symbol_rate: f32) -> Self {
+        SymbolSyncDesign {
+            detector,
+            symbol_rate,
+            bandwidth: 0.002 * symbol_rate,
+            damping: 1.0,
+            deviation: 0.015,
+        }
+    }
+
+    // loop bandwidth in Hz, by default 0.2% of the symbol rate. the
+    // detectors are noisy, so wider costs jitter
+    pub fn bandwidth(mut self, bandwidth: f32) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
+    pub fn damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    // furthest the symbol rate may wander, as a fraction of it
+    pub fn deviation(mut self, deviation: f32) -> Self {
+        self.deviation = deviation;
+        self
+    }
+}
+
+// the matched filter and its derivative, each cut into ARMS phases
+#[derive(Clone, Debug)]
+struct Bank {
+    half: usize,
+    filter: Vec<Vec<f32>>,
+    derivative: Vec<Vec<f32>>,
+    // slope of the detector at lock, per symbol
+    gain: f32,
+}
+
+impl Bank {
+    fn new(pulse: Pulse, sps: f32, span: usize) -> Self {
+        let half = Pulse::half(sps, span);
+        let len = 2 * half + 1;
+        // time in symbols of tap k of arm p
+        let time = |p: usize, k: usize| {
+            (k as f32 - half as f32 + p as f32 / ARMS as f32) / sps
+        };
+        // arm 0 is the plain pulse, and sets the scale for everyone
+        let plain: Vec<f32> = (0..len).map(|k| pulse.at(time(0, k)))
+            .collect();
+        let scale = pulse.scale(&plain, sps);
+        let dt = 0.25 / (ARMS as f32 * sps);
+
+        let arms = |f: &dyn Fn(f32) -> f32| (0..ARMS).map(|p| {
+            (0..len).map(|k| f(time(p, k)) * scale).collect()
+        }).collect();
+        Bank {
+            half,
+            filter: arms(&|t| pulse.at(t)),
+            // per sample, not per symbol
+            derivative: arms(&|t| {
+                (pulse.at(t + dt) - pulse.at(t - dt)) / (2.0 * dt * sps)
+            }),
+            gain: Bank::gain(pulse, span),
+        }
+    }
+
+    // for unit power symbols through the pulse twice, g, the detector
+    // averages the sum of g g' over every symbol. its slope at lock is
+    // -g''(0) - sum g'(k)^2, which swings a lot with rolloff
+    fn gain(pulse: Pulse, span: usize) -> f32 {
+        let step = 1.0 / ARMS as f32;
+        let steps = (span * ARMS / 2) as isize;
+        let g = |t: f32| (-steps..=steps).map(|i| {
+            let s = i as f32 * step;
+            pulse.at(s) * pulse.at(t - s)
+        }).sum::<f32>();
+        let peak = g(0.0);
+        let span = span as isize;
+        -(-span..=span).map(|k| {
+            let t = k as f32;
+            let (a, b, c) = (g(t - step) / peak, g(t) / peak,
+                             g(t + step) / peak);
+            let first = (c - a) / (2.0 * step);
+            let second = (c - 2.0 * b + a) / (step * step);
+            first * first + b * second
+        }).sum::<f32>()
+    }
+
+    // the filter or derivative at fractional position i + frac
+    fn apply(&self, arms: &[Vec<f32>], buffer: &VecDeque<Complex<f32>>,
+             i: usize, frac: f32) -> Complex<f32>
+    {
+        let mut p = (frac * ARMS as f32).round() as usize;
+        let mut i = i;
+        if p == ARMS {
+            p = 0;
+            i += 1;
+        }
+        // tap k goes with the sample k - half before i
+        let start = i + self.half;
+        arms[p].iter().enumerate().fold(Complex::zero(), |acc, (k, h)| {
+            acc + buffer[start - k] * h
+        })
+    }
+}
+
+// cubic lagrange interpolation at i + frac
+fn cubic(buffer: &VecDeque<Complex<f32>>, i: usize, f: f32) -> Complex<f32> {
+    let c = [
+        -f * (f - 1.0) * (f - 2.0) / 6.0,
+        (f + 1.0) * (f - 1.0) * (f - 2.0) / 2.0,
+        -(f + 1.0) * f * (f - 2.0) / 2.0,
+        (f + 1.0) * f * (f - 1.0) / 6.0,
+    ];
+    buffer[i - 1] * c[0] + buffer[i] * c[1]
+        + buffer[i + 1] * c[2] + buffer[i + 2] * c[3]
+}
+
+fn slice(v: Complex<f32>) -> Complex<f32> {
+    let sign = |x: f32| if x >= 0.0 { 1.0 } else { -1.0 };
+    Complex::new(sign(v.re), sign(v.im))
+}
+
+// recovers symbol timing, and yields one symbol at a time along with the
+// timing error that went into the loop, to watch it lock
+#[derive(Clone, Debug)]
+pub struct SymbolSync<S> {
+    signal: S,
+    detector: TimingDetector,
+    symbol_rate: f32,
+    // samples per symbol, nominally
+    period: f32,
+    control: ControlLoop,
+    bank: Option<Bank>,
+
+    // samples needed before and after the interpolation point
+    behind: usize,
+    ahead: usize,
+    buffer: VecDeque<Complex<f32>>,
+    // position of the next symbol in buffer
+    position: f32,
+    // last step, and last symbol
+    step: f32,
+    last: Complex<f32>,
+}
+
+impl<S> SymbolSync<S> where S: Signal<Sample=Complex<f32>> {
+    pub(crate) fn new(signal: S, design: SymbolSyncDesign) -> Self {
+        let period = signal.rate() / design.symbol_rate;
+        // room for a step back to the midpoint, for Gardner. the loop
+        // moves at most half a symbol at once, so a step is at most 1.5
+        let mut behind = (period * 0.75).ceil() as usize + 2;
+        let mut ahead = 2;
+        let bank = match design.detector {
+            TimingDetector::Polyphase(pulse, span) => {
+                let bank = Bank::new(pulse, period, span);
+                behind = behind.max(bank.half);
+                ahead = bank.half + 1;
+                Some(bank)
+            },
+            _ => None,
+        };
+        // loop phase runs over one symbol in 2 pi
+        let control = ControlLoop::new(design.bandwidth, design.damping,
+                                       design.symbol_rate)
+            .limit(2.0 * PI * design.deviation);
+        SymbolSync {
+            signal,
+            detector: design.detector,
+            symbol_rate: design.symbol_rate,
+            period,
+            control,
+            bank,
+            behind,
+            ahead,
+            buffer: std::iter::repeat(Complex::zero()).take(behind).collect(),
+            position: behind as f32,
+            step: period,
+            last: Complex::zero(),
+        }
+    }
+
+    // current samples per symbol estimate
+    pub fn samples_per_symbol(&self) -> f32 {
+        self.period * (1.0 + self.control.frequency / (2.0 * PI))
+    }
+
+    fn interpolate(&self, position: f32) -> Complex<f32> {
+        let i = position.floor() as usize;
+        let frac = position - i as f32;
+        match self.bank {
+            Some(ref bank) => bank.apply(&bank.filter, &self.buffer, i, frac),
+            None => cubic(&self.buffer, i, frac),
+        }
+    }
+}
+
+impl<S> Signal for SymbolSync<S> where S: Signal<Sample=Complex<f32>> {
+    type Sample = (Complex<f32>, f32);
+    fn next(&mut self) -> Option<Self::Sample> {
+        let i = self.position.floor() as usize;
+        while self.buffer.len() <= i + self.ahead {
+            self.buffer.push_back(self.signal.next()?);
+        }
+
+        let value = self.interpolate(self.position);
+        // roughly in symbols for unit symbols and moderate rolloff,
+        // positive when sampling early
+        let error = match self.detector {
+            TimingDetector::Gardner => {
+                let mid = self.interpolate(self.position - self.step / 2.0);
+                ((self.last - value) * mid.conj()).re
+            },
+            TimingDetector::MuellerMuller => {
+                (slice(self.last).conj() * value
+                 - slice(value).conj() * self.last).re / MM_GAIN
+            },
+            TimingDetector::Polyphase(..) => {
+                let bank = self.bank.as_ref().unwrap();
+                let frac = self.position - i as f32;
+                let slope = bank.apply(&bank.derivative, &self.buffer, i, frac);
+                (value * slope.conj()).re * self.period / bank.gain
+            },
+        };
+        let error = error.max(-0.5).min(0.5);
+        self.last = value;
+
+        // the loop runs in phase, so step by however far it moved
+        let before = self.control.phase;
+        self.control.advance(2.0 * PI * error);
+        let mut moved = self.control.phase - before;
+        if moved > PI {
+            moved -= 2.0 * PI;
+        } else if moved < -PI {
+            moved += 2.0 * PI;
+        }
+        self.step = self.period * (1.0 + moved / (2.0 * PI));
+        self.position += self.step;
+
+        // forget what we won't look at again
+        while self.position > (self.behind + 1) as f32 {
+            self.buffer.pop_front();
+            self.position -= 1.0;
+        }
+
+        Some((value, error))
+    }
+    fn rate(&self) -> f32 {
+        self.symbol_rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::PulseD;
+    use crate::signal::{modulate, prbs, Modulation};
+
+    const PULSE: Pulse = Pulse::RootRaisedCosine(0.35);
+
+    #[test]
+    fn polyphase_gain() {
+        // -g''(0) - sum g'(k)^2 for a raised cosine, worked out by hand
+        let gain = Bank::gain(PULSE, 10);
+        assert!((gain - 1.73).abs() < 0.02, "gain {}", gain);
+    }
+
+    // mean error and EVM in dB, over the last thousand symbols
+    fn settle(detector: TimingDetector) -> (f32, f32) {
+        let symbol_rate = 1000.0;
+        // a third of a symbol late, and 0.1% faster than we think
+        let received = modulate(Modulation::Qpsk, symbol_rate, 4, PULSE,
+                                prbs(1).take(2 * 4000))
+            .skip(0.33 / symbol_rate);
+        let design = SymbolSyncDesign::new(detector, symbol_rate / 1.001)
+            .bandwidth(0.005 * symbol_rate);
+        let symbols: Vec<(Complex<f32>, f32)> = match detector {
+            TimingDetector::Polyphase(..) =>
+                received.symbol_sync(design).iter().collect(),
+            _ => received.filter(PulseD(PULSE, symbol_rate, 10))
+                .symbol_sync(design).iter().collect(),
+        };
+        let tail = &symbols[symbols.len() - 1100..symbols.len() - 100];
+        let error = tail.iter().map(|(_, e)| e).sum::<f32>()
+            / tail.len() as f32;
+        let evm = tail.iter().map(|(v, _)| {
+            (v - slice(*v) / 2f32.sqrt()).norm_sqr()
+        }).sum::<f32>() / tail.len() as f32;
+        (error, 10.0 * evm.log10())
+    }
+
+    #[test]
+    fn locks() {
+        for &detector in &[TimingDetector::Gardner,
+                           TimingDetector::MuellerMuller,
+                           TimingDetector::Polyphase(PULSE, 10)] {
+            let (error, evm) = settle(detector);
+            assert!(error.abs() < 0.05, "{:?} error {}", detector, error);
+            assert!(evm < -15.0, "{:?} evm {} dB", detector, evm);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::Signal;
+use crate::filter::{self, Biquad};
+use crate::resample;
+use super::{Block, Filter, Map, Resample};
+
+use num::Complex;
+
+// rate for the multiplex signal, comfortably above the 57kHz RDS carrier
+const MPX_RATE: f32 = 48000.0 * 3.0;
+
+type Discriminator = filter::Pll<
+    Biquad<f32, Complex<f32>>, filter::Identity, Biquad<f32, f32>>;
+type Mpx<S> = Block<Resample<Block<
+    Map<Filter<S, Discriminator>, fn(Option<f32>) -> f32>>>>;
+
+fn normalize(f: Option<f32>) -> f32 {
+    f.unwrap_or(0.0) / filter::WbfmDesign::DEVIATION
+}
+
+#[derive(Debug)]
+pub struct WbfmStereo<S>
+where
+    S: Signal<Sample=Complex<f32>> + Send + 'static,
+{
+    signal: Resample<Block<Filter<Mpx<S>, filter::Wbfm>>>,
+    status: filter::WbfmStatus,
+}
+
+impl<S> WbfmStereo<S>
+where
+    S: Signal<Sample=Complex<f32>> + Send + 'static,
+{
+    pub(crate) fn new(signal: S, design: filter::WbfmDesign, rate: f32)
+                      -> Self
+    {
+        let status = design.status();
+        // blocks between the heavy stages, so they run in parallel
+        let mpx = signal.filter(filter::WbfmDesign::discriminator())
+            .map(normalize as fn(Option<f32>) -> f32)
+            .block(0.1)
+            .resample_with(resample::ConverterType::SincFastest, MPX_RATE)
+            .block(0.1);
+        WbfmStereo {
+            signal: mpx.filter(design).block(0.1).resample(rate),
+            status,
+        }
+    }
+
+    pub fn status(&self) -> filter::WbfmStatus {
+        self.status.clone()
+    }
+}
+
+impl<S> Signal for WbfmStereo<S>
+where
+    S: Signal<Sample=Complex<f32>> + Send + 'static,
+{
+    type Sample = (f32, f32);
+    fn next(&mut self) -> Option<Self::Sample> {
+        self.signal.next()
+    }
+    fn rate(&self) -> f32 {
+        self.signal.rate()
+    }
+}
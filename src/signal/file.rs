@@ -0,0 +1,119 @@
+use super::Signal;
+
+use std::io::{BufReader, Read, Result, Write};
+use std::path::Path;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use num::Complex;
+
+// interleaved I/Q, as dumped by rtl_sdr and friends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IqFormat {
+    Cu8,
+    Cs8,
+    Cs16,
+    Cf32,
+}
+
+impl IqFormat {
+    // .cu8, .cs8, .cs16, .cf32, and rtl_sdr's usual .bin
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "cu8" | "bin" => Some(IqFormat::Cu8),
+            "cs8" => Some(IqFormat::Cs8),
+            "cs16" => Some(IqFormat::Cs16),
+            "cf32" | "cfile" => Some(IqFormat::Cf32),
+            _ => None,
+        }
+    }
+
+    // bytes per complex sample
+    pub fn size(&self) -> usize {
+        match self {
+            IqFormat::Cu8 | IqFormat::Cs8 => 2,
+            IqFormat::Cs16 => 4,
+            IqFormat::Cf32 => 8,
+        }
+    }
+
+    fn read_one<R: Read>(&self, r: &mut R) -> Result<f32> {
+        Ok(match self {
+            IqFormat::Cu8 => (r.read_u8()? as f32 - 128.0) / 128.0,
+            IqFormat::Cs8 => r.read_i8()? as f32 / 128.0,
+            IqFormat::Cs16 => r.read_i16::<LittleEndian>()? as f32 / 32768.0,
+            IqFormat::Cf32 => r.read_f32::<LittleEndian>()?,
+        })
+    }
+
+    fn write_one<W: Write>(&self, w: &mut W, v: f32) -> Result<()> {
+        match self {
+            IqFormat::Cu8 => {
+                let v = (v * 128.0 + 128.0).round().max(0.0).min(255.0);
+                w.write_u8(v as u8)
+            },
+            IqFormat::Cs8 => {
+                let v = (v * 128.0).round().max(-128.0).min(127.0);
+                w.write_i8(v as i8)
+            },
+            IqFormat::Cs16 => {
+                let v = (v * 32768.0).round().max(-32768.0).min(32767.0);
+                w.write_i16::<LittleEndian>(v as i16)
+            },
+            IqFormat::Cf32 => w.write_f32::<LittleEndian>(v),
+        }
+    }
+
+    // samples are scaled to (-1.0, 1.0), like RtlTcpSignal
+    pub fn read<R: Read>(&self, r: &mut R) -> Result<Complex<f32>> {
+        let re = self.read_one(r)?;
+        let im = self.read_one(r)?;
+        Ok(Complex::new(re, im))
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W, v: Complex<f32>) -> Result<()> {
+        self.write_one(w, v.re)?;
+        self.write_one(w, v.im)
+    }
+}
+
+#[derive(Debug)]
+pub struct FromReader<R> {
+    reader: R,
+    format: IqFormat,
+    rate: f32,
+}
+
+impl<R> FromReader<R> where R: Read {
+    pub fn new(reader: R, format: IqFormat, rate: f32) -> Self {
+        FromReader {
+            reader,
+            format,
+            rate,
+        }
+    }
+}
+
+impl<R> Signal for FromReader<R> where R: Read {
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        self.format.read(&mut self.reader).ok()
+    }
+    fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+pub fn from_reader<R>(reader: R, format: IqFormat, rate: f32) -> FromReader<R>
+where
+    R: Read,
+{
+    FromReader::new(reader, format, rate)
+}
+
+pub fn from_file<P>(path: P, format: IqFormat, rate: f32)
+                    -> Result<FromReader<BufReader<std::fs::File>>>
+where
+    P: AsRef<Path>,
+{
+    let file = BufReader::new(std::fs::File::open(path)?);
+    Ok(FromReader::new(file, format, rate))
+}
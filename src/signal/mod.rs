@@ -7,6 +7,15 @@ mod times;
 mod sources;
 pub use sources::*;
 
+mod file;
+pub use file::*;
+
+mod nco;
+pub use nco::*;
+
+mod modulate;
+pub use modulate::*;
+
 mod adapters;
 pub use adapters::*;
 
@@ -23,8 +32,35 @@ pub trait Signal {
         Block::new(self, size)
     }
 
-    fn decimate(self, rate: f32) -> Decimate<Self> where Self: Sized {
-        Decimate::new(self, rate)
+    // splits the whole band into evenly spaced channels, each at
+    // 1 / channels the rate. channel 0 is centered on 0Hz
+    fn channelize(self, channels: usize) -> Vec<Channel<Self>>
+    where
+        Self: Signal<Sample=num::Complex<f32>> + Sized,
+    {
+        Channel::split(self, channels)
+    }
+
+    fn decimate(self, rate: f32) -> Result<Decimator<Self>, DecimateError>
+    where
+        Self::Sample: filter::Convolve<f32>,
+        Self: Sized,
+    {
+        self.decimate_with(DecimateMode::Auto, rate)
+    }
+
+    fn decimate_with(self, mode: DecimateMode, rate: f32)
+                     -> Result<Decimator<Self>, DecimateError>
+    where
+        Self::Sample: filter::Convolve<f32>,
+        Self: Sized,
+    {
+        Decimator::new(self, mode, rate)
+    }
+
+    // no anti-aliasing! for picking out frames, not for signals
+    fn downsample(self, rate: f32) -> Downsample<Self> where Self: Sized {
+        Downsample::new(self, rate)
     }
 
     fn enumerate(self) -> Enumerate<Self> where Self: Sized {
@@ -75,6 +111,14 @@ pub trait Signal {
         self.filter(filter::MonitorD(rate, f))
     }
 
+    fn record<W>(self, writer: W, format: IqFormat) -> Record<Self, W>
+    where
+        W: std::io::Write,
+        Self: Signal<Sample=num::Complex<f32>> + Sized,
+    {
+        Record::new(self, writer, format)
+    }
+
     fn resample(self, rate: f32) -> Resample<Self>
     where
         Self::Sample: resample::Resample,
@@ -83,13 +127,21 @@ pub trait Signal {
         self.resample_with(resample::ConverterType::SincBestQuality, rate)
     }
 
-    fn resample_with(self, typ: resample::ConverterType, rate: f32)
-                     -> Resample<Self>
+    // a resample::ConverterType, or resample::Polyphase
+    fn resample_with<M>(self, mode: M, rate: f32) -> M::Signal
     where
-        Self::Sample: resample::Resample,
+        M: ResampleMode<Self>,
         Self: Sized,
     {
-        Resample::new(self, typ, rate)
+        mode.resample(self, rate)
+    }
+
+    // in Hz, adjustable later through Shift::control()
+    fn shift(self, frequency: f32) -> Shift<Self>
+    where
+        Self: Signal<Sample=num::Complex<f32>> + Sized,
+    {
+        Shift::new(self, frequency)
     }
 
     fn skip(self, duration: f32) -> Skip<Self>
@@ -99,6 +151,17 @@ pub trait Signal {
         Skip::new(self, duration)
     }
 
+    // events hears about every open and close
+    fn squelch<D, F>(self, design: SquelchDesign<D>, events: F)
+                     -> Squelch<Self, D, F>
+    where
+        D: SquelchDetector<Self::Sample>,
+        F: FnMut(SquelchEvent),
+        Self: Sized,
+    {
+        Squelch::new(self, design, events)
+    }
+
     fn stereo(self) -> Stereo<Self>
     where
         Self: Sized,
@@ -106,6 +169,14 @@ pub trait Signal {
         Stereo::new(self)
     }
 
+    // one (symbol, timing error) per symbol
+    fn symbol_sync(self, design: SymbolSyncDesign) -> SymbolSync<Self>
+    where
+        Self: Signal<Sample=num::Complex<f32>> + Sized,
+    {
+        SymbolSync::new(self, design)
+    }
+
     fn take(self, duration: f32) -> Take<Self>
     where
         Self: Sized,
@@ -113,6 +184,14 @@ pub trait Signal {
         Take::new(self, duration)
     }
 
+    fn wbfm_stereo(self, design: filter::WbfmDesign, rate: f32)
+                   -> WbfmStereo<Self>
+    where
+        Self: Signal<Sample=num::Complex<f32>> + Send + Sized + 'static,
+    {
+        WbfmStereo::new(self, design, rate)
+    }
+
     fn window(self, duration: f32) -> Window<Self>
     where
         Self::Sample: num::Zero + Clone,
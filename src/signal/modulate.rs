@@ -0,0 +1,318 @@
+use super::Signal;
+use crate::filter::Pulse;
+
+use num::{Complex, Zero};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+// pulses span this many symbols, so every symbol is delayed by half
+const SPAN: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Modulation {
+    Bpsk,
+    Qpsk,
+    Psk8,
+    Qam16,
+    Qam64,
+    // with modulation index, the spacing between neighboring tones over
+    // the symbol rate. with a gaussian pulse, Fsk2 is GFSK
+    Fsk2(f32),
+    Fsk4(f32),
+}
+
+fn gray_decode(mut g: u32) -> u32 {
+    let mut b = 0;
+    while g != 0 {
+        b ^= g;
+        g >>= 1;
+    }
+    b
+}
+
+fn gray_encode(b: u32) -> u32 {
+    b ^ (b >> 1)
+}
+
+// the bits on one axis, gray coded, to a level in -(m-1) ..= m-1
+fn level(bits: u32, m: u32) -> f32 {
+    (2 * gray_decode(bits)) as f32 - (m - 1) as f32
+}
+
+// and back, from the nearest level
+fn unlevel(x: f32, m: u32) -> u32 {
+    let i = ((x + (m - 1) as f32) / 2.0).round();
+    gray_encode(i.max(0.0).min((m - 1) as f32) as u32)
+}
+
+impl Modulation {
+    pub fn bits_per_symbol(&self) -> usize {
+        use Modulation::*;
+        match *self {
+            Bpsk | Fsk2(_) => 1,
+            Qpsk | Fsk4(_) => 2,
+            Psk8 => 3,
+            Qam16 => 4,
+            Qam64 => 6,
+        }
+    }
+
+    pub fn is_fsk(&self) -> bool {
+        matches!(*self, Modulation::Fsk2(_) | Modulation::Fsk4(_))
+    }
+
+    // the symbol for some bits, most significant first. constellations
+    // are gray coded with unit average power, and line up with Psk for
+    // Costas. FSK symbols are frequency levels, -1 and 1 or -3 to 3
+    pub fn map(&self, bits: &[bool]) -> Complex<f32> {
+        use Modulation::*;
+        let v = bits.iter().fold(0, |v, &b| (v << 1) | b as u32);
+        let axes = |n: u32, m: u32, scale: f32| {
+            Complex::new(level(v >> n, m), level(v & ((1 << n) - 1), m))
+                / scale
+        };
+        match *self {
+            Bpsk => Complex::new(level(v, 2), 0.0),
+            Qpsk => axes(1, 2, 2f32.sqrt()),
+            Psk8 => {
+                let phase = 2.0 * PI * gray_decode(v) as f32 / 8.0;
+                Complex::from_polar(&1.0, &phase)
+            },
+            Qam16 => axes(2, 4, 10f32.sqrt()),
+            Qam64 => axes(3, 8, 42f32.sqrt()),
+            Fsk2(_) => Complex::new(level(v, 2), 0.0),
+            Fsk4(_) => Complex::new(level(v, 4), 0.0),
+        }
+    }
+
+    // the bits for the nearest symbol, most significant first. for FSK,
+    // give it the frequency level as the real part
+    pub fn demap(&self, v: Complex<f32>) -> Vec<bool> {
+        use Modulation::*;
+        let axes = |n: u32, m: u32, scale: f32| {
+            let v = v * scale;
+            (unlevel(v.re, m) << n) | unlevel(v.im, m)
+        };
+        let value = match *self {
+            Bpsk | Fsk2(_) => unlevel(v.re, 2),
+            Qpsk => axes(1, 2, 2f32.sqrt()),
+            Psk8 => {
+                let i = (v.arg() / (2.0 * PI) * 8.0).round() as i32;
+                gray_encode(i.rem_euclid(8) as u32)
+            },
+            Qam16 => axes(2, 4, 10f32.sqrt()),
+            Qam64 => axes(3, 8, 42f32.sqrt()),
+            Fsk4(_) => unlevel(v.re, 4),
+        };
+        let n = self.bits_per_symbol();
+        (0..n).rev().map(|i| (value >> i) & 1 == 1).collect()
+    }
+}
+
+// a baseband modulated signal, from bits. the first symbol starts at the
+// first sample, and a short partial symbol at the end is padded with
+// zeros. it runs until the last pulse dies out
+#[derive(Clone, Debug)]
+pub struct Modulator<I> {
+    bits: I,
+    modulation: Modulation,
+    rate: f32,
+    sps: usize,
+    taps: Vec<f32>,
+
+    // newest symbol last, enough to cover the pulse
+    symbols: VecDeque<Complex<f32>>,
+    // sample within the current symbol
+    count: usize,
+    // samples left once the bits run out
+    remaining: Option<usize>,
+    // for FSK, in radians
+    phase: f32,
+}
+
+impl<I> Modulator<I> where I: Iterator<Item=bool> {
+    // RRC or rectangular pulses for PSK and QAM, gaussian or rectangular
+    // for FSK. the others swing negative, and aren't frequency pulses.
+    // sps is a whole number so symbols line up with samples
+    pub fn new(modulation: Modulation, symbol_rate: f32, sps: usize,
+               pulse: Pulse, bits: I) -> Self
+    {
+        if modulation.is_fsk() {
+            assert!(matches!(pulse, Pulse::Rectangular | Pulse::Gaussian(_)),
+                    "FSK needs a rectangular or gaussian pulse, not {:?}",
+                    pulse);
+        }
+        let taps = pulse.taps(sps as f32, SPAN);
+        let depth = (taps.len() + sps - 1) / sps;
+        Modulator {
+            bits,
+            modulation,
+            rate: symbol_rate * sps as f32,
+            sps,
+            taps,
+            symbols: std::iter::repeat(Complex::zero()).take(depth).collect(),
+            count: 0,
+            remaining: None,
+            phase: 0.0,
+        }
+    }
+
+    // from a symbol going in, to the middle of its pulse coming out, in
+    // seconds. always a whole number of symbols
+    pub fn delay(&self) -> f32 {
+        (self.taps.len() / 2) as f32 / self.rate
+    }
+
+    fn next_symbol(&mut self) -> Option<Complex<f32>> {
+        let n = self.modulation.bits_per_symbol();
+        let mut bits = Vec::with_capacity(n);
+        for bit in &mut self.bits {
+            bits.push(bit);
+            if bits.len() == n {
+                break;
+            }
+        }
+        if bits.is_empty() {
+            return None;
+        }
+        bits.resize(n, false);
+        Some(self.modulation.map(&bits))
+    }
+}
+
+impl<I> Signal for Modulator<I> where I: Iterator<Item=bool> {
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        if self.count == 0 && self.remaining.is_none() {
+            let symbol = match self.next_symbol() {
+                Some(symbol) => symbol,
+                None => {
+                    // the last real symbol is still a pulse away
+                    self.remaining = Some(self.taps.len() - self.sps);
+                    Complex::zero()
+                },
+            };
+            self.symbols.pop_front();
+            self.symbols.push_back(symbol);
+        } else if self.count == 0 {
+            self.symbols.pop_front();
+            self.symbols.push_back(Complex::zero());
+        }
+        if let Some(ref mut remaining) = self.remaining {
+            if *remaining == 0 {
+                return None;
+            }
+            *remaining -= 1;
+        }
+
+        let shaped = self.symbols.iter().rev().enumerate()
+            .map(|(j, s)| (j * self.sps + self.count, s))
+            .take_while(|(k, _)| *k < self.taps.len())
+            .fold(Complex::zero(), |acc, (k, s)| acc + s * self.taps[k]);
+        self.count = (self.count + 1) % self.sps;
+
+        match self.modulation {
+            Modulation::Fsk2(h) | Modulation::Fsk4(h) => {
+                // a whole pulse moves the phase by pi h per level
+                self.phase += PI * h * shaped.re / self.sps as f32;
+                self.phase %= 2.0 * PI;
+                Some(Complex::from_polar(&1.0, &self.phase))
+            },
+            _ => Some(shaped),
+        }
+    }
+    fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+pub fn modulate<I>(modulation: Modulation, symbol_rate: f32, sps: usize,
+                   pulse: Pulse, bits: I) -> Modulator<I>
+where
+    I: Iterator<Item=bool>,
+{
+    Modulator::new(modulation, symbol_rate, sps, pulse, bits)
+}
+
+// PRBS-15 test bits, the x^15 + x^14 + 1 sequence. never all zero
+#[derive(Clone, Debug)]
+pub struct Prbs {
+    state: u16,
+}
+
+impl Prbs {
+    pub fn new(seed: u16) -> Self {
+        Prbs {
+            state: if seed & 0x7fff == 0 { 1 } else { seed & 0x7fff },
+        }
+    }
+}
+
+impl Iterator for Prbs {
+    type Item = bool;
+    fn next(&mut self) -> Option<bool> {
+        let bit = ((self.state >> 14) ^ (self.state >> 13)) & 1;
+        self.state = ((self.state << 1) | bit) & 0x7fff;
+        Some(bit == 1)
+    }
+}
+
+pub fn prbs(seed: u16) -> Prbs {
+    Prbs::new(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::PulseD;
+
+    const ALL: [Modulation; 7] = [
+        Modulation::Bpsk, Modulation::Qpsk, Modulation::Psk8,
+        Modulation::Qam16, Modulation::Qam64,
+        Modulation::Fsk2(0.5), Modulation::Fsk4(0.25),
+    ];
+
+    #[test]
+    fn map_demap() {
+        for &modulation in ALL.iter() {
+            let n = modulation.bits_per_symbol();
+            for v in 0..1u32 << n {
+                let bits: Vec<bool> = (0..n).rev()
+                    .map(|i| (v >> i) & 1 == 1).collect();
+                let symbol = modulation.map(&bits);
+                assert_eq!(modulation.demap(symbol), bits,
+                           "{:?} {:?}", modulation, symbol);
+            }
+        }
+    }
+
+    #[test]
+    fn no_bit_errors() {
+        let (symbol_rate, sps, symbols) = (1000.0, 4, 500);
+        let pulse = Pulse::RootRaisedCosine(0.35);
+        for &modulation in ALL[..5].iter() {
+            let n = modulation.bits_per_symbol();
+            let sent: Vec<bool> = prbs(1).take(n * symbols).collect();
+            let modulator = modulate(modulation, symbol_rate, sps, pulse,
+                                     sent.clone().into_iter());
+            // the matched filter is the same pulse, so twice the delay
+            let delay = (2.0 * modulator.delay() * modulator.rate())
+                .round() as usize;
+            let received: Vec<Complex<f32>> = modulator
+                .filter(PulseD(pulse, symbol_rate, SPAN)).iter().collect();
+            let got: Vec<bool> = (0..symbols)
+                .flat_map(|k| modulation.demap(received[delay + k * sps]))
+                .collect();
+            let errors = sent.iter().zip(got.iter())
+                .filter(|(a, b)| a != b).count();
+            assert_eq!(errors, 0, "{:?}", modulation);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn fsk_needs_frequency_pulse() {
+        modulate(Modulation::Fsk2(0.5), 1000.0, 4,
+                 Pulse::RootRaisedCosine(0.35), prbs(1));
+    }
+}
@@ -0,0 +1,94 @@
+use super::Signal;
+
+use num::Complex;
+use std::sync::Arc;
+
+// table entries per cycle, interpolated linearly. spurs are ~130dB down
+const TABLE_BITS: u32 = 12;
+const TABLE_SIZE: usize = 1 << TABLE_BITS;
+const FRAC_BITS: u32 = 32 - TABLE_BITS;
+
+// a numerically controlled oscillator. the phase is a wrapping u32, so it
+// never drifts or needs renormalizing, however long it runs
+#[derive(Clone, Debug)]
+pub struct Nco {
+    // one extra entry on the end, so interpolation never wraps
+    table: Arc<Vec<Complex<f32>>>,
+    rate: f32,
+    frequency: f32,
+    phase: u32,
+    step: u32,
+}
+
+impl Nco {
+    pub fn new(rate: f32, frequency: f32) -> Self {
+        let table = (0..=TABLE_SIZE).map(|i| {
+            let phase = 2.0 * std::f64::consts::PI * i as f64
+                / TABLE_SIZE as f64;
+            Complex::new(phase.cos() as f32, phase.sin() as f32)
+        }).collect();
+        let mut nco = Nco {
+            table: Arc::new(table),
+            rate,
+            frequency: 0.0,
+            phase: 0,
+            step: 0,
+        };
+        nco.set_frequency(frequency);
+        nco
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    // in Hz. negative is fine, and keeps the phase continuous
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+        let cycles = (frequency as f64 / self.rate as f64).fract();
+        self.step = (cycles * 4294967296.0).round() as i64 as u32;
+    }
+
+    // in radians, from 0 to 2 pi
+    pub fn phase(&self) -> f32 {
+        (self.phase as f64 / 4294967296.0 * 2.0 * std::f64::consts::PI)
+            as f32
+    }
+
+    pub fn set_phase(&mut self, phase: f32) {
+        let cycles = (phase as f64 / (2.0 * std::f64::consts::PI))
+            .rem_euclid(1.0);
+        self.phase = (cycles * 4294967296.0) as u32;
+    }
+
+    // multiply by the next sample, and advance
+    pub fn mix(&mut self, value: Complex<f32>) -> Complex<f32> {
+        value * self.tick()
+    }
+
+    fn tick(&mut self) -> Complex<f32> {
+        let index = (self.phase >> FRAC_BITS) as usize;
+        let frac = (self.phase & ((1 << FRAC_BITS) - 1)) as f32
+            / (1u32 << FRAC_BITS) as f32;
+        let a = self.table[index];
+        let b = self.table[index + 1];
+        self.phase = self.phase.wrapping_add(self.step);
+        let v = a + (b - a) * frac;
+        // interpolating between points on the circle lands inside it
+        v / v.norm()
+    }
+}
+
+impl Signal for Nco {
+    type Sample = Complex<f32>;
+    fn next(&mut self) -> Option<Self::Sample> {
+        Some(self.tick())
+    }
+    fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+pub fn nco(rate: f32, frequency: f32) -> Nco {
+    Nco::new(rate, frequency)
+}
@@ -0,0 +1,77 @@
+use sdr::*;
+use sdr::rtltcp::{DongleInfo, RtlTcp, RtlTcpServer, TunerType};
+
+use num::Complex;
+use std::io::Read;
+
+const RATE: f32 = 1800000.0;
+
+// a carrier right on the server's center frequency
+fn carrier(frequency: u32) -> RtlTcpServer<impl Signal<Sample=Complex<f32>>> {
+    let signal = signal::from_func(RATE, |_| Complex::new(0.5, 0.0));
+    RtlTcpServer::bind("127.0.0.1:0", signal, frequency).unwrap()
+}
+
+// average frequency over some samples, in Hz
+fn frequency<S>(signal: &mut S, samples: usize) -> f32
+where
+    S: Signal<Sample=Complex<f32>>,
+{
+    let mut last = signal.next().unwrap();
+    let mut turned = 0.0f64;
+    for _ in 0..samples {
+        let v = signal.next().unwrap();
+        turned += (v * last.conj()).arg() as f64;
+        last = v;
+    }
+    (turned / samples as f64 * RATE as f64 / (2.0 * std::f64::consts::PI))
+        as f32
+}
+
+#[test]
+fn header() {
+    let server = carrier(100000000);
+    let addr = server.local_addr().unwrap();
+    server.spawn();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    let mut id = [0; 12];
+    stream.read_exact(&mut id).unwrap();
+    assert_eq!(&id[..4], b"RTL0");
+    assert_eq!(DongleInfo::parse(&id).unwrap(),
+               DongleInfo::new(TunerType::R820T));
+}
+
+#[test]
+fn retune() {
+    // off the 8 Hz grid f32 has up here, so rounding would show
+    let center = 100000003;
+    let server = carrier(center);
+    let addr = server.local_addr().unwrap();
+    server.spawn();
+
+    let (mut signal, control) = RtlTcp::new()
+        .address(addr).unwrap()
+        .rate(RATE as u32).unwrap()
+        .frequency(center).unwrap()
+        .listen_with_control().unwrap();
+    assert_eq!(control.info().tuner, TunerType::R820T);
+    assert!(frequency(&mut signal, 10000).abs() < 1.0);
+
+    // tuning up moves the carrier down, once the old samples drain
+    let tuned = 100200005;
+    control.set_frequency(tuned).unwrap();
+    let expected = (center as i64 - tuned as i64) as f32;
+    let mut moved = false;
+    for _ in 0..(10.0 * RATE) as usize / 1000 {
+        if frequency(&mut signal, 1000).abs() > 1000.0 {
+            moved = true;
+            break;
+        }
+    }
+    assert!(moved, "carrier never moved");
+    // skip past the chunk where it switched
+    frequency(&mut signal, 10000);
+    let got = frequency(&mut signal, 100000);
+    assert!((got - expected).abs() < 1.0, "carrier at {} Hz", got);
+}